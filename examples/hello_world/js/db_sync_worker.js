@@ -1,89 +1,316 @@
 import sqlite3InitModule from './sqlite3.mjs';
 
+// Resolves when the query worker has replaced the database with a pulled snapshot.
+let pendingReplace = null;
+
+// Syncs the database once for each message, posting an event for each step of the sync and
+// `{ done: true }` at the end, with `error: { kind, message, status }` if it has failed.
 self.onmessage = async function (message) {
-    const { app_id, directory_name, file_name, endpoint, force } = message.data;
-    
-    self.sqlite3InitModule().then((sqlite3) => {
-	if (sqlite3.capi.sqlite3_vfs_find("opfs")) {
-	    const db = new sqlite3.oo1.OpfsDb(`${directory_name}/${file_name}`, "ct");
-	    const query = "select id, password from sync_connections order by created_at asc limit 1;";
-	    const result = db.exec(query, { rowMode: 'array' });
-
-	    if (0 < result.length && 1 < result[0].length) {
-		const user_id = result[0][0];
-		const password = result[0][1];
-
-		if (force !== "none") {
-		    sync(app_id, user_id, password, directory_name, file_name, endpoint, force);
-		    setInterval(sync, 30000, app_id, user_id, password, directory_name, file_name, endpoint, null);
-		} else {
-		    setInterval(sync, 30000, app_id, user_id, password, directory_name, file_name, endpoint, force);
-		}
-	    }
+    if (message.data.replaced !== undefined) {
+	const { replaced, error } = message.data;
+	const pending = pendingReplace;
+	pendingReplace = null;
+
+	if (replaced) {
+	    pending.resolve();
 	} else {
-	    console.error("OPFS not available because of your browser capability.");
+	    pending.reject(error);
 	}
-    });
-};
+	return;
+    }
+
+    const { app_id, directory_name, file_name, endpoint, encrypted, passphrase, force } = message.data;
 
-async function sync(app_id, user_id, password, directory_name, file_name, endpoint, force) {
-    console.log('Syncing..');
-    
     try {
-	const file = await getFile(directory_name, file_name);
-
-	const last_modified = file === null || force === "remote" ? 0 : Math.floor(file.lastModified / 1000);
-
-	const response = await fetch(endpoint, {
-	    method: "POST",
-	    headers: { "Content-Type": "application/json" },
-	    body: JSON.stringify({ app_id, user_id, password, file_name, unix_timestamp: last_modified })
-	});
-	
-	if (!response.ok) {
-	    console.error("Sync API is not working now");
-	    return;
+	const sqlite3 = await self.sqlite3InitModule();
+
+	if (!sqlite3.capi.sqlite3_vfs_find("opfs")) {
+	    throw syncError("database", "OPFS not available because of your browser capability.");
 	}
 
-	const json = await response.json();
+	const db = new sqlite3.oo1.OpfsDb(`${directory_name}/${file_name}`, "ct");
+	const query = "select id, password from sync_connections order by created_at asc limit 1;";
 
-	const obj = JSON.parse(json);
-	
-	if (obj.presigned_url === null || obj.last_modified_url === null || obj.action === null) {
-	    console.log("No need to sync your database");
-	    return;
+	let result = [];
+
+	try {
+	    result = db.exec(query, { rowMode: 'array' });
+	} catch (err) {
+	    // The app hasn't connected to a sync server yet.
+	    if (!/no such table/.test(err.message)) {
+		throw syncError("database", err.message);
+	    }
+	} finally {
+	    db.close();
 	}
 
-	if (obj.action === "get_object") {
-	    const res = await fetch(obj.presigned_url, { method: "GET" });
-	    
-	    const fileHandler = await getFileHandler(directory_name, file_name, file === null);
+	if (0 < result.length && 1 < result[0].length) {
+	    const user_id = result[0][0];
+	    const password = result[0][1];
 
-	    if (fileHandler === null) {
-		return;
-	    }
-	    
-	    const fileAccessHandler = await fileHandler.createSyncAccessHandle();
-
-	    const arrayBuffer = await res.arrayBuffer();
-	    const uint8Array = new Uint8Array(arrayBuffer);
-
-	    fileAccessHandler.write(uint8Array, { at: 0 });
-	    fileAccessHandler.flush();
-	    
-	    fileAccessHandler.close();
-	} else if (obj.action === "put_object") {
-	    const arrayBuffer = await file.arrayBuffer();	    
-	    await Promise.all([
-		fetch(obj.presigned_url, { method: "PUT", headers: { "Content-Type": "application/vnd.sqlite3" }, body: arrayBuffer }),
-		fetch(obj.last_modified_url, { method: "PUT", headers: { "Content-Type": "text/plain" }, body: new File([last_modified], "LASTMODIFIED", { type: "text/plain" }) })
-	    ]);
+	    const cipher = encrypted ? { passphrase } : null;
+
+	    await sync(app_id, user_id, password, directory_name, file_name, endpoint, cipher, force);
 	}
 
-	console.log('Synced');
+	self.postMessage({ done: true });
     } catch (err) {
-	console.error(err.message);
-    }    
+	self.postMessage({ done: true, error: toSyncError(err) });
+    }
+};
+
+function syncError(kind, message, status) {
+    return { kind, message, status };
+}
+
+function toSyncError(err) {
+    if (typeof err.kind === "string") {
+	return err;
+    }
+
+    // fetch rejects with a TypeError when the request couldn't be sent.
+    return syncError(err instanceof TypeError ? "network" : "unknown", err.message ?? String(err));
+}
+
+// Which copy wins is decided by SHA-256 hashes of the database rather than by timestamps:
+// `hash` is of the database on this device and `base_hash` is of the one which this device
+// last synced with. A device which hasn't changed since then pulls the server copy, and a
+// server copy which hasn't changed since then is overwritten by the device. If both have
+// changed, the server copy wins.
+//
+// With `cipher`, the database is encrypted before it's uploaded and decrypted after it's
+// downloaded, so the sync server never sees its contents. The hashes are then keyed by the
+// passphrase so that the server can't check a guess of the contents against them, though it
+// can still tell whether two snapshots are the same.
+async function sync(app_id, user_id, password, directory_name, file_name, endpoint, cipher, force) {
+    if (cipher !== null && !cipher.passphrase) {
+	throw syncError("passphrase_required", "Set a passphrase with SyncHandle::set_passphrase to sync the encrypted database");
+    }
+
+    console.log('Syncing..');
+    self.postMessage({ event: "started" });
+
+    const file = await getFile(directory_name, file_name);
+    const arrayBuffer = file === null ? null : await file.arrayBuffer();
+
+    const hashOf = (data) => cipher === null ? digest(data) : keyedDigest(data, cipher.passphrase, app_id, user_id);
+
+    const hash = arrayBuffer === null || force === "remote" ? null : await hashOf(arrayBuffer);
+    const base_hash = await readSyncedHash(directory_name, file_name);
+
+    const response = await fetch(endpoint, {
+	method: "POST",
+	headers: { "Content-Type": "application/json" },
+	body: JSON.stringify({ app_id, user_id, password, file_name, hash, base_hash }),
+	credentials: "include"
+    });
+
+    if (!response.ok) {
+	throw syncError("http", `Sync API responded with ${response.status}`, response.status);
+    }
+
+    const json = await response.json();
+
+    const obj = JSON.parse(json);
+
+    if (obj.presigned_url === null || obj.hash_url === null || obj.action === null) {
+	if (hash !== null) {
+	    await writeSyncedHash(directory_name, file_name, hash);
+	}
+
+	console.log("No need to sync your database");
+	self.postMessage({ event: "up_to_date" });
+	return;
+    }
+
+    if (obj.action === "get_object") {
+	const res = await fetch(obj.presigned_url, { method: "GET" });
+
+	if (!res.ok) {
+	    throw syncError("http", `Failed to pull the database: ${res.status}`, res.status);
+	}
+
+	const downloaded = new Uint8Array(await res.arrayBuffer());
+	const uint8Array = cipher === null ? downloaded : await decrypt(downloaded, cipher.passphrase);
+
+	const pulledHash = await hashOf(uint8Array);
+
+	await replaceDatabase(uint8Array);
+	await writeSyncedHash(directory_name, file_name, pulledHash);
+
+	self.postMessage({ event: "pulled" });
+    } else if (obj.action === "put_object") {
+	const body = cipher === null ? arrayBuffer : await encrypt(new Uint8Array(arrayBuffer), cipher.passphrase);
+	const contentType = cipher === null ? "application/vnd.sqlite3" : "application/octet-stream";
+
+	const responses = await Promise.all([
+	    fetch(obj.presigned_url, { method: "PUT", headers: { "Content-Type": contentType }, body }),
+	    fetch(obj.hash_url, { method: "PUT", headers: { "Content-Type": "text/plain" }, body: hash })
+	]);
+
+	const failed = responses.find((res) => !res.ok);
+
+	if (failed !== undefined) {
+	    throw syncError("http", `Failed to push the database: ${failed.status}`, failed.status);
+	}
+
+	await writeSyncedHash(directory_name, file_name, hash);
+
+	self.postMessage({ event: "pushed" });
+    }
+
+    console.log('Synced');
+}
+
+// An encrypted snapshot is laid out as MAGIC, the PBKDF2 iterations (u32, big endian), the
+// salt, the IV and then the AES-GCM ciphertext.
+const MAGIC = new TextEncoder().encode("ROCALENC1");
+const PBKDF2_ITERATIONS = 600000;
+const SALT_LENGTH = 16;
+const IV_LENGTH = 12;
+
+// Deriving a key takes a while on purpose, so the last one is reused while the passphrase and
+// salt stay the same.
+let derivedKey = null;
+
+async function deriveKey(passphrase, salt, iterations) {
+    if (derivedKey !== null && derivedKey.passphrase === passphrase && derivedKey.iterations === iterations && equalBytes(derivedKey.salt, salt)) {
+	return derivedKey.key;
+    }
+
+    const material = await crypto.subtle.importKey("raw", new TextEncoder().encode(passphrase), "PBKDF2", false, ["deriveKey"]);
+    const key = await crypto.subtle.deriveKey(
+	{ name: "PBKDF2", salt, iterations, hash: "SHA-256" },
+	material,
+	{ name: "AES-GCM", length: 256 },
+	false,
+	["encrypt", "decrypt"]
+    );
+
+    derivedKey = { passphrase, salt, iterations, key };
+
+    return key;
+}
+
+async function encrypt(plaintext, passphrase) {
+    const salt = derivedKey !== null && derivedKey.passphrase === passphrase ? derivedKey.salt : crypto.getRandomValues(new Uint8Array(SALT_LENGTH));
+    const iterations = derivedKey !== null && derivedKey.passphrase === passphrase ? derivedKey.iterations : PBKDF2_ITERATIONS;
+    const iv = crypto.getRandomValues(new Uint8Array(IV_LENGTH));
+
+    const key = await deriveKey(passphrase, salt, iterations);
+    const ciphertext = new Uint8Array(await crypto.subtle.encrypt({ name: "AES-GCM", iv }, key, plaintext));
+
+    const header = new Uint8Array(MAGIC.length + 4);
+    header.set(MAGIC);
+    new DataView(header.buffer).setUint32(MAGIC.length, iterations);
+
+    const encrypted = new Uint8Array(header.length + SALT_LENGTH + IV_LENGTH + ciphertext.length);
+    encrypted.set(header);
+    encrypted.set(salt, header.length);
+    encrypted.set(iv, header.length + SALT_LENGTH);
+    encrypted.set(ciphertext, header.length + SALT_LENGTH + IV_LENGTH);
+
+    return encrypted;
+}
+
+async function decrypt(encrypted, passphrase) {
+    // A plaintext snapshot isn't taken even if it was uploaded before encryption was turned on,
+    // since the server could put any database there. It's replaced when a device pushes.
+    if (!equalBytes(encrypted.subarray(0, MAGIC.length), MAGIC)) {
+	throw syncError("decryption", "The pulled database isn't encrypted by Rocal");
+    }
+
+    const iterations = new DataView(encrypted.buffer, encrypted.byteOffset + MAGIC.length, 4).getUint32(0);
+    const saltStart = MAGIC.length + 4;
+    const salt = encrypted.slice(saltStart, saltStart + SALT_LENGTH);
+    const iv = encrypted.slice(saltStart + SALT_LENGTH, saltStart + SALT_LENGTH + IV_LENGTH);
+
+    const key = await deriveKey(passphrase, salt, iterations);
+
+    try {
+	return new Uint8Array(await crypto.subtle.decrypt({ name: "AES-GCM", iv }, key, encrypted.subarray(saltStart + SALT_LENGTH + IV_LENGTH)));
+    } catch {
+	throw syncError("decryption", "Failed to decrypt the pulled database. The passphrase may be wrong");
+    }
+}
+
+let hashKey = null;
+
+// The key is derived from the passphrase like the one of the snapshots, with a salt of its own
+// so that it's never the same key.
+async function keyedDigest(data, passphrase, app_id, user_id) {
+    const salt = `rocal-sync-hash:${app_id}:${user_id}`;
+
+    if (hashKey === null || hashKey.passphrase !== passphrase || hashKey.salt !== salt) {
+	const material = await crypto.subtle.importKey("raw", new TextEncoder().encode(passphrase), "PBKDF2", false, ["deriveKey"]);
+	const key = await crypto.subtle.deriveKey(
+	    { name: "PBKDF2", salt: new TextEncoder().encode(salt), iterations: PBKDF2_ITERATIONS, hash: "SHA-256" },
+	    material,
+	    { name: "HMAC", hash: "SHA-256" },
+	    false,
+	    ["sign"]
+	);
+
+	hashKey = { passphrase, salt, key };
+    }
+
+    const hash = await crypto.subtle.sign("HMAC", hashKey.key, data);
+
+    return Array.from(new Uint8Array(hash), (byte) => byte.toString(16).padStart(2, "0")).join("");
+}
+
+function equalBytes(a, b) {
+    return a.length === b.length && a.every((byte, i) => byte === b[i]);
+}
+
+async function digest(data) {
+    const hash = await crypto.subtle.digest("SHA-256", data);
+
+    return Array.from(new Uint8Array(hash), (byte) => byte.toString(16).padStart(2, "0")).join("");
+}
+
+async function readSyncedHash(directory_name, file_name) {
+    try {
+	const root = await navigator.storage.getDirectory();
+	const dirHandler = await root.getDirectoryHandle(directory_name);
+	const fileHandler = await dirHandler.getFileHandle(`${file_name}.synced`);
+	const hash = await (await fileHandler.getFile()).text();
+
+	return hash === "" ? null : hash;
+    } catch {
+	return null;
+    }
+}
+
+function writeSyncedHash(directory_name, file_name, hash) {
+    return writeFile(directory_name, `${file_name}.synced`, new TextEncoder().encode(hash));
+}
+
+// The query worker keeps the database open, so it's asked through the main thread to replace
+// the file with its connection closed instead of the file being written here under it.
+function replaceDatabase(uint8Array) {
+    return new Promise((resolve, reject) => {
+	pendingReplace = { resolve, reject };
+	self.postMessage({ replace: uint8Array });
+    });
+}
+
+async function writeFile(directory_name, file_name, uint8Array) {
+    const fileHandler = await getFileHandler(directory_name, file_name, true);
+
+    if (fileHandler === null) {
+	throw syncError("database", `Cannot write ${file_name}`);
+    }
+
+    const fileAccessHandler = await fileHandler.createSyncAccessHandle();
+
+    try {
+	fileAccessHandler.truncate(uint8Array.byteLength);
+	fileAccessHandler.write(uint8Array, { at: 0 });
+	fileAccessHandler.flush();
+    } finally {
+	fileAccessHandler.close();
+    }
 }
 
 async function getFile(directory_name, file_name) {
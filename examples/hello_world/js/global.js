@@ -1,16 +1,437 @@
-function execSQL(db, query) {
+let queryWorker = null;
+let storageFallback = "memory";
+let kvvfsDb = null;
+let nextQueryId = 0;
+let nextTransactionId = 0;
+const pendingQueries = new Map();
+const changeListeners = [];
+const syncs = new Map();
+const syncListeners = [];
+const MAX_SYNC_BACKOFF = 15 * 60 * 1000;
+let online = navigator.onLine !== false;
+
+// Where databases are kept when OPFS isn't available, and the database which kvvfs keeps.
+function setStorageFallback(fallback, db) {
+    storageFallback = fallback;
+    kvvfsDb = db;
+}
+
+// Resolves to where queries are posted: the query worker, or the same module run on the main
+// thread when kvvfs has to keep the database instead of OPFS because workers can't reach
+// localStorage or sessionStorage.
+function getQueryWorker() {
+    if (queryWorker !== null) {
+	return queryWorker;
+    }
+
+    const worker = new Worker("./js/db_query_worker.js", { type: 'module' });
+
+    worker.onmessage = function (message) {
+	receiveQueryMessage(message.data);
+    };
+
+    worker.onerror = function (err) {
+	for (const pending of pendingQueries.values()) {
+	    pending.reject({ kind: "worker", message: err.message });
+	}
+
+	pendingQueries.clear();
+	worker.terminate();
+	queryWorker = null;
+    };
+
+    const init = { storage_fallback: storageFallback, kvvfs_db: kvvfsDb };
+
+    queryWorker = sendQueryMessage(worker, init).then(async ({ opfs }) => {
+	if (opfs || (storageFallback !== "local_storage" && storageFallback !== "session_storage")) {
+	    return worker;
+	}
+
+	worker.terminate();
+
+	const module = await import("./db_query_worker.js");
+	const local = { postMessage: module.receive };
+
+	module.connect(receiveQueryMessage);
+	await sendQueryMessage(local, init);
+
+	return local;
+    });
+
+    return queryWorker;
+}
+
+function receiveQueryMessage(data) {
+    const { id, result, error, changes } = data;
+
+    if (changes !== undefined) {
+	for (const listener of changeListeners) {
+	    listener(changes.db, changes.tables);
+	}
+	return;
+    }
+    const pending = pendingQueries.get(id);
+
+    if (pending === undefined) {
+	return;
+    }
+
+    pendingQueries.delete(id);
+
+    if (error !== undefined) {
+	pending.reject(error);
+    } else {
+	pending.resolve(result);
+    }
+}
+
+function sendQueryMessage(worker, message) {
     return new Promise((resolve, reject) => {
-	const worker = new Worker("./js/db_query_worker.js", { type: 'module' });
-	worker.postMessage({ db: db, query: query });
+	const id = nextQueryId++;
+	pendingQueries.set(id, { resolve, reject });
+	worker.postMessage({ id: id, ...message });
+    });
+}
 
+function postQueryMessage(message) {
+    return getQueryWorker().then((worker) => sendQueryMessage(worker, message));
+}
+
+function databaseStorage(db) {
+    return postQueryMessage({ db: db, action: "storage" });
+}
+
+function execSQL(db, query, bindings, transaction, limit, column) {
+    return postQueryMessage({ db: db, query: query, bindings: bindings, transaction: transaction, limit: limit, column: column });
+}
+
+function newTransactionId() {
+    return nextTransactionId++;
+}
+
+// The writes of an `untracked` transaction aren't recorded as changes to push.
+function beginTransaction(db, transaction, untracked) {
+    return postQueryMessage({ db: db, action: "begin", transaction: transaction, untracked: untracked });
+}
+
+function commitTransaction(db, transaction) {
+    return postQueryMessage({ db: db, action: "commit", transaction: transaction });
+}
+
+function rollbackTransaction(db, transaction) {
+    return postQueryMessage({ db: db, action: "rollback", transaction: transaction });
+}
+
+function exportDatabase(db) {
+    return postQueryMessage({ db: db, action: "export" });
+}
+
+// `bytes` is copied since it's a view of the wasm memory, which could change before it's posted.
+function importDatabase(db, bytes, migrations) {
+    return postQueryMessage({ db: db, action: "import", bytes: bytes.slice(), migrations: migrations });
+}
+
+function queryTables(db, query) {
+    return postQueryMessage({ db: db, action: "tables", query: query });
+}
+
+function onDatabaseChange(listener) {
+    changeListeners.push(listener);
+}
+
+function trackChanges(db) {
+    return postQueryMessage({ db: db, action: "track_changes" });
+}
+
+function startChangesetSync(db, app_id, endpoint, resolveConflict) {
+    startSync(db, (emit) => syncChangesets(db, app_id, endpoint, resolveConflict, emit), true);
+}
+
+// Snapshots are read from and written to OPFS, so a database kept anywhere else isn't synced.
+async function startSnapshotSync(db, app_id, directory_name, file_name, endpoint, encrypted) {
+    const storage = await databaseStorage(db);
+
+    if (storage !== "opfs") {
+	console.warn(`${db} isn't synced because it's kept in ${storage} instead of OPFS.`);
+	return;
+    }
+
+    const worker = new Worker("./js/db_sync_worker.js", { type: 'module' });
+
+    startSync(db, (emit) => new Promise((resolve, reject) => {
 	worker.onmessage = function (message) {
-	    resolve(message.data);
-	    worker.terminate();
-	};
+	    const { event, done, error, replace } = message.data;
 
-	worker.onerror = function (err) {
-	    reject(err);
-	    worker.terminate();	    
+	    // A pulled snapshot is written by the query worker, which holds the database open.
+	    if (replace !== undefined) {
+		postQueryMessage({ db: db, action: "replace", bytes: replace })
+		    .then(() => worker.postMessage({ replaced: true }))
+		    .catch((err) => worker.postMessage({ replaced: false, error: { kind: "database", message: err.message } }));
+	    } else if (event !== undefined) {
+		emit({ event });
+	    } else if (done && error !== undefined) {
+		reject(error);
+	    } else if (done) {
+		resolve();
+	    }
 	};
+
+	const passphrase = getSync(db).passphrase;
+	worker.postMessage({ app_id, directory_name, file_name, endpoint, encrypted, passphrase, force: "none" });
+    }), false);
+}
+
+function getSync(db) {
+    if (!syncs.has(db)) {
+	syncs.set(db, { run: null, interval: 30000, paused: false, running: null, timer: null, lastSyncedAt: null, passphrase: null, failures: 0 });
+    }
+
+    return syncs.get(db);
+}
+
+// Runs `run` every interval until paused. `run` reports its progress with `emit` after it
+// has found something to sync with.
+function startSync(db, run, immediate) {
+    const sync = getSync(db);
+
+    sync.run = run;
+
+    if (immediate) {
+	syncNow(db);
+    } else {
+	scheduleSync(db);
+    }
+}
+
+// Waits for the interval, or longer after failed syncs in a row: the wait doubles with each
+// failure up to MAX_SYNC_BACKOFF, with a random half of it as jitter so that devices don't retry
+// in lockstep. Nothing is scheduled while offline.
+function scheduleSync(db) {
+    const sync = getSync(db);
+
+    clearTimeout(sync.timer);
+    sync.timer = null;
+
+    if (sync.run === null || sync.paused || sync.running !== null || !online) {
+	return;
+    }
+
+    let delay = sync.interval;
+
+    if (0 < sync.failures) {
+	const backoff = Math.min(MAX_SYNC_BACKOFF, sync.interval * 2 ** (sync.failures - 1));
+	delay = backoff / 2 + Math.random() * backoff / 2;
+    }
+
+    sync.timer = setTimeout(syncNow, delay, db);
+}
+
+function syncNow(db) {
+    const sync = getSync(db);
+
+    if (sync.run === null || sync.running !== null) {
+	return;
+    }
+
+    clearTimeout(sync.timer);
+
+    let started = false;
+
+    const emit = (event) => {
+	if (event.event === "started") {
+	    started = true;
+	}
+
+	for (const listener of syncListeners) {
+	    listener(db, event);
+	}
+    };
+
+    if (!online) {
+	emit({ event: "failed", error: { kind: "offline", message: "The browser is offline" } });
+	return;
+    }
+
+    // A sync counts as done only once all of it has succeeded, and only if it has found
+    // something to sync with.
+    sync.running = sync.run(emit)
+	.then(() => {
+	    sync.failures = 0;
+
+	    if (started) {
+		sync.lastSyncedAt = Date.now();
+		emit({ event: "finished" });
+	    }
+	})
+	.catch((err) => {
+	    const error = toSyncError(err);
+	    console.error(error.message);
+
+	    sync.failures++;
+	    emit({ event: "failed", error });
+	})
+	.finally(() => {
+	    sync.running = null;
+	    scheduleSync(db);
+	});
+}
+
+const SYNC_ERROR_KINDS = ["offline", "http", "network", "passphrase_required", "decryption", "database", "unknown"];
+
+// Errors from the sync worker and postSyncRequest already are sync errors. The others come from
+// the query worker, which also has a kind, or from fetch.
+function toSyncError(err) {
+    if (SYNC_ERROR_KINDS.includes(err.kind)) {
+	return err;
+    }
+
+    if (typeof err.kind === "string") {
+	return { kind: "database", message: err.message };
+    }
+
+    return { kind: err instanceof TypeError ? "network" : "unknown", message: err.message ?? String(err) };
+}
+
+addEventListener("offline", () => {
+    online = false;
+
+    for (const db of syncs.keys()) {
+	scheduleSync(db);
+    }
+});
+
+addEventListener("online", () => {
+    online = true;
+
+    for (const [db, sync] of syncs) {
+	sync.failures = 0;
+
+	if (!sync.paused) {
+	    syncNow(db);
+	}
+    }
+});
+
+function pauseSync(db) {
+    getSync(db).paused = true;
+    scheduleSync(db);
+}
+
+function resumeSync(db) {
+    getSync(db).paused = false;
+    scheduleSync(db);
+}
+
+function isSyncPaused(db) {
+    return getSync(db).paused;
+}
+
+function setSyncInterval(db, interval) {
+    getSync(db).interval = interval;
+    scheduleSync(db);
+}
+
+// The passphrase is only kept in memory, so it has to be set again after the app is reloaded.
+function setSyncPassphrase(db, passphrase) {
+    getSync(db).passphrase = passphrase;
+}
+
+function syncPassphrase(db) {
+    return getSync(db).passphrase;
+}
+
+function lastSyncedAt(db) {
+    return getSync(db).lastSyncedAt;
+}
+
+function onSyncEvent(listener) {
+    syncListeners.push(listener);
+}
+
+// Pulls the changes made on other devices and then pushes the ones made on this device.
+async function syncChangesets(db, app_id, endpoint, resolveConflict, emit) {
+    const connections = await execSQL(db, "select id, password from sync_connections order by created_at asc limit 1;", [], undefined, 1, undefined);
+
+    if (connections.length === 0) {
+	return;
+    }
+
+    console.log('Syncing..');
+    emit({ event: "started" });
+
+    const { device_id, cursor } = await postQueryMessage({ db: db, action: "sync_state" });
+    const file_name = db.substring(db.lastIndexOf("/") + 1);
+    const credentials = { app_id, user_id: connections[0].id, password: connections[0].password, device_id, file_name };
+
+    const pulled = await postSyncRequest(endpoint, { ...credentials, action: "pull_changesets", cursor });
+    const changesets = pulled.changesets.map(decodeBase64);
+
+    const transaction = await beginTransaction(db, newTransactionId(), false);
+
+    try {
+	if (0 < changesets.length) {
+	    const conflicts = await postQueryMessage({ db: db, action: "apply_changesets", transaction, changesets });
+	    const decisions = conflicts.map((conflict) => ({ key: conflict.key, resolution: resolveConflict(conflict) }));
+
+	    await postQueryMessage({ db: db, action: "apply_changesets", transaction, changesets, decisions });
+	}
+
+	await postQueryMessage({ db: db, action: "set_cursor", transaction, cursor: pulled.cursor });
+	await commitTransaction(db, transaction);
+    } catch (err) {
+	await rollbackTransaction(db, transaction);
+	throw err;
+    }
+
+    if (0 < changesets.length) {
+	emit({ event: "pulled" });
+    }
+
+    const pending = await postQueryMessage({ db: db, action: "pending_changesets" });
+
+    if (pending !== null) {
+	// The server refuses the push if another device has pushed since the pull above,
+	// in which case it is retried after pulling again on the next sync.
+	const pushed = await postSyncRequest(endpoint, { ...credentials, action: "push_changeset", cursor: pulled.cursor, changeset: encodeBase64(pending.changeset) });
+
+	await postQueryMessage({ db: db, action: "mark_pushed", up_to: pending.up_to });
+	await postQueryMessage({ db: db, action: "set_cursor", cursor: pushed.cursor });
+
+	emit({ event: "pushed" });
+    } else if (changesets.length === 0) {
+	emit({ event: "up_to_date" });
+    }
+
+    console.log('Synced');
+}
+
+async function postSyncRequest(endpoint, body) {
+    const response = await fetch(endpoint, {
+	method: "POST",
+	headers: { "Content-Type": "application/json" },
+	body: JSON.stringify(body),
+	credentials: "include"
     });
+
+    if (!response.ok) {
+	throw { kind: "http", message: `Sync API responded with ${response.status}`, status: response.status };
+    }
+
+    const json = await response.json();
+
+    return typeof json === "string" ? JSON.parse(json) : json;
+}
+
+function encodeBase64(bytes) {
+    let binary = "";
+
+    for (let i = 0; i < bytes.length; i += 0x8000) {
+	binary += String.fromCharCode(...bytes.subarray(i, i + 0x8000));
+    }
+
+    return btoa(binary);
+}
+
+function decodeBase64(text) {
+    return Uint8Array.from(atob(text), (c) => c.charCodeAt(0));
 }
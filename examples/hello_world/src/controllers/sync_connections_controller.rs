@@ -47,7 +47,7 @@ impl SyncConnectionsController {
                 let db_sync_worker = DbSyncWorker::new("./js/db_sync_worker.js", ForceType::Remote);
                 db_sync_worker.run();
             }
-            Err(err) => web_sys::console::error_1(&err),
+            Err(err) => web_sys::console::error_1(&err.into()),
         }
     }
 }
@@ -1,8 +1,6 @@
 use std::sync::Arc;
 
-use wasm_bindgen::JsValue;
-
-use crate::{models::sync_connection::SyncConnection, Database};
+use crate::{models::sync_connection::SyncConnection, Database, DatabaseError};
 
 pub struct SyncConnectionRepository {
     database: Arc<Database>,
@@ -13,26 +11,25 @@ impl SyncConnectionRepository {
         SyncConnectionRepository { database }
     }
 
-    pub async fn get(&self) -> Result<Option<SyncConnection>, JsValue> {
-        let mut result: Vec<SyncConnection> = self
+    pub async fn get(&self) -> Result<Option<SyncConnection>, DatabaseError> {
+        let result: Option<SyncConnection> = self
             .database
             .query("select id from sync_connections limit 1;")
-            .fetch()
+            .fetch_optional()
             .await?;
 
-        match result.pop() {
+        match result {
             Some(conn) => Ok(Some(SyncConnection::new(conn.get_id().to_string()))),
             None => Ok(None),
         }
     }
 
-    pub async fn create(&self, id: &str, password: &str) -> Result<(), JsValue> {
+    pub async fn create(&self, id: &str, password: &str) -> Result<(), DatabaseError> {
         match self
             .database
-            .query(&format!(
-                "insert into sync_connections (id, password) values ('{}', '{}')",
-                id, password
-            ))
+            .query("insert into sync_connections (id, password) values ($1, $2)")
+            .bind(id)
+            .bind(password)
             .execute()
             .await
         {
@@ -33,6 +33,9 @@ self.addEventListener('activate', (e) => {
     );
 });
 
+// The history routing mode is told by registering the worker as `./sw.js?routing_mode=history`.
+const historyRouting = new URL(self.location).searchParams.get("routing_mode") === "history";
+
 self.addEventListener('fetch', (e) => {
     if (e.request.method !== "GET") {
 	return;
@@ -42,27 +45,38 @@ self.addEventListener('fetch', (e) => {
 
     const url = new URL(e.request.url);
 
+    // Every page of the app is index.html in the history routing mode, so that a deep link
+    // opens even while offline. Other navigations, e.g. to pages next to an app served on a
+    // sub-path in the hash routing mode, are left as they are.
+    const request = historyRouting && e.request.mode === "navigate" && !url.pathname.split("/").pop().includes(".")
+	  ? new Request("./index.html")
+	  : e.request;
+
     if (isOnline) {
-	e.respondWith(staleWhileRevalidate(e));
+	e.respondWith(staleWhileRevalidate(request));
     } else {
-	e.respondWith(cacheOnly(e));
+	e.respondWith(cacheOnly(request));
     }
 });
 
-function cacheOnly(e) {
-    return caches.match(e.request);
+function cacheOnly(request) {
+    return caches.match(request);
 }
 
-function staleWhileRevalidate(ev) {
-  return caches.match(ev.request).then((cacheResponse) => {
-    let fetchResponse = fetch(ev.request).then((response) => {
-      return caches.open(version).then((cache) => {
-        cache.put(ev.request, response.clone());
-        return response;
-      });
+function staleWhileRevalidate(request) {
+    return caches.match(request).then((cacheResponse) => {
+	let fetchResponse = fetch(request).then((response) => {
+	    if (response.ok) {
+		return caches.open(version).then((cache) => {
+		    cache.put(request, response.clone());
+		    return response;
+		});		
+	    }
+
+	    return cacheResponse;
+	});
+	return cacheResponse || fetchResponse;
     });
-    return cacheResponse || fetchResponse;
-  });
 }
 
 function networkRevalidateAndCache(ev) {
@@ -25,9 +25,9 @@ impl CartsController {
     pub async fn add(&self, product_id: u32) {
         let cart_repo = CartRepository::new(CONFIG.database.clone());
 
-        if let Err(Some(err)) = cart_repo.add_item(product_id).await {
+        if let Err(err) = cart_repo.add_item(product_id).await {
             if let Ok(mut flash) = FLASH_MEMORY.lock() {
-                let _ = flash.set("add_item_to_cart_error", &err);
+                let _ = flash.set("add_item_to_cart_error", &err.to_string());
             }
             return;
         }
@@ -42,9 +42,9 @@ impl CartsController {
     pub async fn delete(&self, product_id: u32) {
         let cart_repo = CartRepository::new(CONFIG.database.clone());
 
-        if let Err(Some(err)) = cart_repo.remove_item(product_id).await {
+        if let Err(err) = cart_repo.remove_item(product_id).await {
             if let Ok(mut flash) = FLASH_MEMORY.lock() {
-                let _ = flash.set("delete_item_from_cart_error", &err);
+                let _ = flash.set("delete_item_from_cart_error", &err.to_string());
             }
             return;
         }
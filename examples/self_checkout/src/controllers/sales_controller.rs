@@ -85,16 +85,9 @@ impl SalesController {
             return;
         };
 
-        if let Err(Some(err)) = sales_repo.create(items).await {
+        if let Err(err) = sales_repo.create(items).await {
             if let Ok(mut flash) = FLASH_MEMORY.lock() {
-                let _ = flash.set("sales_repo.create", &err);
-            }
-            return;
-        }
-
-        if let Err(Some(err)) = cart_repo.remove_all_items().await {
-            if let Ok(mut flash) = FLASH_MEMORY.lock() {
-                let _ = flash.set("cart_repo.remove_all_items", &err);
+                let _ = flash.set("sales_repo.create", &err.to_string());
             }
             return;
         }
@@ -2,5 +2,6 @@ pub mod cart_item;
 pub mod flash_memory;
 pub mod product;
 pub mod sales;
+pub mod sales_id;
 pub mod sales_item;
 pub mod sales_log;
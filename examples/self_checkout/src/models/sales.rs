@@ -1,6 +1,6 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Sales {
     product_id: u32,
     product_name: String,
@@ -22,20 +22,4 @@ impl Sales {
             number_of_items,
         }
     }
-
-    pub fn get_product_id(&self) -> &u32 {
-        &self.product_id
-    }
-
-    pub fn get_product_name(&self) -> &str {
-        &self.product_name
-    }
-
-    pub fn get_product_price(&self) -> &f64 {
-        &self.product_price
-    }
-
-    pub fn get_number_of_items(&self) -> &u32 {
-        &self.number_of_items
-    }
 }
@@ -0,0 +1,12 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct SalesId {
+    id: u32,
+}
+
+impl SalesId {
+    pub fn get_id(&self) -> &u32 {
+        &self.id
+    }
+}
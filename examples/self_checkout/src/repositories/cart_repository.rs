@@ -1,4 +1,4 @@
-use crate::{models::cart_item::CartItem, Database};
+use crate::{models::cart_item::CartItem, Database, DatabaseError};
 use std::sync::Arc;
 
 pub struct CartRepository {
@@ -10,7 +10,7 @@ impl CartRepository {
         Self { database }
     }
 
-    pub async fn get_all_items(&self) -> Result<Vec<CartItem>, Option<String>> {
+    pub async fn get_all_items(&self) -> Result<Vec<CartItem>, DatabaseError> {
         let result: Vec<CartItem> = self
             .database
             .query(
@@ -26,16 +26,15 @@ impl CartRepository {
             "#,
             )
             .fetch()
-            .await
-            .map_err(|err| err.as_string())?;
+            .await?;
 
         Ok(result)
     }
 
-    pub async fn add_item(&self, product_id: u32) -> Result<(), Option<String>> {
-        let mut items: Vec<CartItem> = self
+    pub async fn add_item(&self, product_id: u32) -> Result<(), DatabaseError> {
+        let item: Option<CartItem> = self
             .database
-            .query(&format!(
+            .query(
                 r#"
                select
                  c.id as id,
@@ -45,60 +44,42 @@ impl CartRepository {
                  c.number_of_items as number_of_items
                from cart_items as c
                inner join products as p on c.product_id = p.id
-               where p.id = {} limit 1;"#,
-                product_id
-            ))
-            .fetch()
-            .await
-            .map_err(|err| err.as_string())?;
+               where p.id = $1 limit 1;"#,
+            )
+            .bind(product_id)
+            .fetch_optional()
+            .await?;
 
-        match items.pop() {
+        match item {
             Some(item) => {
                 let number_of_items = item.get_number_of_items() + 1;
                 self.database
-                    .query(&format!(
-                        "update cart_items set number_of_items = {} where product_id = {}",
-                        number_of_items, product_id
-                    ))
+                    .query("update cart_items set number_of_items = $1 where product_id = $2")
+                    .bind(number_of_items)
+                    .bind(product_id)
                     .execute()
-                    .await
-                    .map_err(|err| err.as_string())?;
+                    .await?;
             }
             None => {
                 let number_of_items = 1;
                 self.database
-                    .query(&format!(
-                        "insert into cart_items (product_id, number_of_items) values ({}, {})",
-                        product_id, number_of_items
-                    ))
+                    .query("insert into cart_items (product_id, number_of_items) values ($1, $2)")
+                    .bind(product_id)
+                    .bind(number_of_items)
                     .execute()
-                    .await
-                    .map_err(|err| err.as_string())?;
+                    .await?;
             }
         };
 
         Ok(())
     }
 
-    pub async fn remove_item(&self, product_id: u32) -> Result<(), Option<String>> {
-        self.database
-            .query(&format!(
-                "delete from cart_items where product_id = {}",
-                product_id
-            ))
-            .execute()
-            .await
-            .map_err(|err| err.as_string())?;
-
-        Ok(())
-    }
-
-    pub async fn remove_all_items(&self) -> Result<(), Option<String>> {
+    pub async fn remove_item(&self, product_id: u32) -> Result<(), DatabaseError> {
         self.database
-            .query("delete from cart_items;")
+            .query("delete from cart_items where product_id = $1")
+            .bind(product_id)
             .execute()
-            .await
-            .map_err(|err| err.as_string())?;
+            .await?;
 
         Ok(())
     }
@@ -1,4 +1,4 @@
-use crate::{models::product::Product, Database};
+use crate::{models::product::Product, Database, DatabaseError};
 use std::sync::Arc;
 
 pub struct ProductRepository {
@@ -10,13 +10,12 @@ impl ProductRepository {
         Self { database }
     }
 
-    pub async fn get_all(&self) -> Result<Vec<Product>, Option<String>> {
+    pub async fn get_all(&self) -> Result<Vec<Product>, DatabaseError> {
         let result: Vec<Product> = self
             .database
             .query("select id, name, price from products;")
             .fetch()
-            .await
-            .map_err(|err| err.as_string())?;
+            .await?;
 
         Ok(result)
     }
@@ -1,6 +1,6 @@
 use crate::{
-    models::{sales::Sales, sales_item::SalesItem, sales_log::SalesLog},
-    Database,
+    models::{sales::Sales, sales_id::SalesId, sales_item::SalesItem, sales_log::SalesLog},
+    Database, DatabaseError,
 };
 use std::sync::Arc;
 
@@ -13,69 +13,58 @@ impl SalesRepository {
         Self { database }
     }
 
-    pub async fn get_all(&self) -> Result<Vec<SalesLog>, Option<String>> {
+    pub async fn get_all(&self) -> Result<Vec<SalesLog>, DatabaseError> {
         let result: Vec<SalesLog> = self
             .database
             .query("select id, created_at from sales order by created_at desc;")
             .fetch()
-            .await
-            .map_err(|err| err.as_string())?;
+            .await?;
 
         Ok(result)
     }
 
-    pub async fn get_all_items(&self, id: u32) -> Result<Vec<SalesItem>, Option<String>> {
+    pub async fn get_all_items(&self, id: u32) -> Result<Vec<SalesItem>, DatabaseError> {
         let result: Vec<SalesItem> = self
             .database
-            .query(&format!(
+            .query(
                 r#"
-                select product_name, product_price, number_of_items from sales_items where sales_id = {}
+                select product_name, product_price, number_of_items from sales_items where sales_id = $1
                 "#,
-                id
-            ))
+            )
+            .bind(id)
             .fetch()
-            .await
-            .map_err(|err| err.as_string())?;
+            .await?;
 
         Ok(result)
     }
 
-    pub async fn create(&self, sales_list: Vec<Sales>) -> Result<(), Option<String>> {
-        let mut values: Vec<String> = vec![];
-
-        for sales in sales_list {
-            values.push(format!(
-                "((select sales_id from sid), {}, '{}', {}, {})",
-                sales.get_product_id(),
-                sales.get_product_name(),
-                sales.get_product_price(),
-                sales.get_number_of_items()
-            ))
-        }
-
-        let values = values.join(",");
-
+    pub async fn create(&self, sales_list: Vec<Sales>) -> Result<(), DatabaseError> {
         self.database
-            .query(&format!(
-                r#"
-                  begin immediate;
-                    insert into sales default values;
+            .transaction(|tx| async move {
+                let sales_id: SalesId = tx
+                    .query("insert into sales default values returning id;")
+                    .fetch_one()
+                    .await?;
 
-                    with sid as (
-                      select last_insert_rowid() as sales_id
+                for sales in sales_list {
+                    tx.query(
+                        r#"
+                        insert into
+                          sales_items (sales_id, product_id, product_name, product_price, number_of_items)
+                        values
+                          (:sales_id, :product_id, :product_name, :product_price, :number_of_items);
+                        "#,
                     )
-                    insert into
-                      sales_items (sales_id, product_id, product_name, product_price, number_of_items)
-                    values
-                      {};
-                  commit;
-                "#,
-                values
-            ))
-            .execute()
-            .await
-            .map_err(|err| err.as_string())?;
+                    .bind_named("sales_id", *sales_id.get_id())
+                    .bind_struct(&sales)
+                    .execute()
+                    .await?;
+                }
 
-        Ok(())
+                tx.query("delete from cart_items;").execute().await?;
+
+                Ok(())
+            })
+            .await
     }
 }
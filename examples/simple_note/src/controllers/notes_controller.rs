@@ -1,8 +1,7 @@
 use rocal::rocal_core::traits::{Controller, SharedRouter};
-use wasm_bindgen::JsValue;
 use web_sys::console;
 
-use crate::{models::note_id::NoteId, views::notes_view::NotesView, CONFIG};
+use crate::{models::note::Note, views::notes_view::NotesView, DatabaseError, CONFIG};
 
 pub struct NotesController {
     router: SharedRouter,
@@ -22,34 +21,36 @@ impl NotesController {
     pub fn create(&self, title: Option<String>, body: Option<String>) {
         let db = CONFIG.get_database().clone();
 
-        let result: Result<Vec<NoteId>, JsValue> = if let (Some(title), Some(body)) = (title, body)
-        {
-            db.query("insert into notes(title, body) values ($1, $2) returning id;")
-                .bind(title)
-                .bind(body)
-                .fetch()
-                .await
+        let result: Result<i64, DatabaseError> = if let (Some(title), Some(body)) = (title, body) {
+            rocal::query!(
+                db,
+                "insert into notes(title, body) values ($1, $2) returning id;",
+                title,
+                body
+            )
+            .fetch_one()
+            .await
+            .map(|note| note.id)
         } else {
-            db.query("insert into notes(title, body) values (null, null) returning id;")
-                .fetch()
-                .await
+            rocal::query!(
+                db,
+                "insert into notes(title, body) values (null, null) returning id;"
+            )
+            .fetch_one()
+            .await
+            .map(|note| note.id)
         };
 
-        let result = match result {
-            Ok(result) => result,
+        match result {
+            Ok(note_id) => {
+                self.router
+                    .borrow()
+                    .redirect(&format!("/?note_id={}", &note_id))
+                    .await;
+            }
             Err(err) => {
-                console::error_1(&err);
-                return;
+                console::error_1(&err.into());
             }
-        };
-
-        if let Some(note_id) = result.get(0) {
-            self.router
-                .borrow()
-                .redirect(&format!("/?note_id={}", &note_id.id))
-                .await;
-        } else {
-            console::error_1(&"Could not add a new note".into());
         }
     }
 
@@ -57,35 +58,23 @@ impl NotesController {
     pub fn update(&self, note_id: i64, title: String, body: String) {
         let db = CONFIG.get_database().clone();
 
-        let result = db
-            .query("update notes set title = $1, body = $2 where id = $3;")
-            .bind(title)
-            .bind(body)
-            .bind(note_id)
-            .execute()
-            .await;
-
-        match result {
-            Ok(_) => {
-                self.router
-                    .borrow()
-                    .redirect(&format!("/?note_id={}", &note_id))
-                    .await;
-            }
-            Err(err) => {
-                console::error_1(&err);
-                return;
-            }
+        let note = Note {
+            id: note_id,
+            title: Some(title.clone()),
+            body: Some(body.clone()),
         };
+
+        // The notes are rendered again by the live query in RootController.
+        if let Err(err) = note.update(&db).await {
+            console::error_1(&err.into());
+        }
     }
 
     #[rocal::action]
     pub fn delete(&self, note_id: i64) {
         let db = CONFIG.get_database().clone();
 
-        let result = db
-            .query("delete from notes where id = $1;")
-            .bind(note_id)
+        let result = rocal::query!(db, "delete from notes where id = $1;", note_id)
             .execute()
             .await;
 
@@ -94,7 +83,7 @@ impl NotesController {
                 self.router.borrow().redirect("/").await;
             }
             Err(err) => {
-                console::error_1(&err);
+                console::error_1(&err.into());
             }
         };
     }
@@ -1,19 +1,25 @@
+use std::cell::RefCell;
+
 use crate::{
     models::note::Note, view_models::root_view_model::RootViewModel, views::root_view::RootView,
-    CONFIG,
+    DatabaseError, Subscription, CONFIG,
 };
 use rocal::rocal_core::traits::{Controller, SharedRouter};
-use wasm_bindgen::JsValue;
 
 pub struct RootController {
     router: SharedRouter,
     view: RootView,
+    subscription: RefCell<Option<Subscription>>,
 }
 
 impl Controller for RootController {
     type View = RootView;
     fn new(router: SharedRouter, view: Self::View) -> Self {
-        RootController { router, view }
+        RootController {
+            router,
+            view,
+            subscription: RefCell::new(None),
+        }
     }
 }
 
@@ -21,23 +27,26 @@ impl RootController {
     #[rocal::action]
     pub fn index(&self, note_id: Option<i64>) {
         let db = CONFIG.get_database().clone();
-        let result: Result<Vec<Note>, JsValue> =
-            db.query("select id, title, body from notes").fetch().await;
+        let view = self.view.clone();
+
+        // The list of notes is rendered again whenever a note is created, updated, or deleted.
+        let subscription = db.watch(
+            &db.query("select id, title, body from notes;"),
+            move |result: Result<Vec<Note>, DatabaseError>| {
+                let notes = result.unwrap_or_default();
 
-        let notes = if let Ok(notes) = result {
-            notes
-        } else {
-            vec![]
-        };
+                let note: Option<Note> = if let Some(note_id) = note_id {
+                    notes.iter().find(|note| note.id == note_id).cloned()
+                } else {
+                    None
+                };
 
-        let note: Option<Note> = if let Some(note_id) = note_id {
-            notes.iter().find(|note| note.id == note_id).cloned()
-        } else {
-            None
-        };
+                let vm = RootViewModel::new(note, notes);
 
-        let vm = RootViewModel::new(note, notes);
+                view.index(vm);
+            },
+        );
 
-        self.view.index(vm);
+        self.subscription.replace(Some(subscription));
     }
 }
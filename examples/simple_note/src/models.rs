@@ -1,2 +1 @@
 pub mod note;
-pub mod note_id;
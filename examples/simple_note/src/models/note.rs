@@ -1,6 +1,6 @@
 use serde::Deserialize;
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, rocal::Model)]
 pub struct Note {
     pub id: i64,
     pub title: Option<String>,
@@ -6,7 +6,7 @@ use rocal::{
     view,
 };
 
-use crate::view_models::root_view_model::RootViewModel;
+use crate::{routes, view_models::root_view_model::RootViewModel};
 
 pub struct RootTemplate {
     router: SharedRouter,
@@ -47,7 +47,7 @@ impl Template for RootTemplate {
                   </div>
                   <div class="col-span-5">
                     if let Some(note) = data.get_note() {
-                      <form action={{ &format!("/notes/{}", &note.id) }} method="patch">
+                      <form action={{ &routes::note_path(note.id) }} method="patch">
                         if let Some(title) = note.get_title() {
                           <input type="text" name="title" placeholder="Title" class="border-none text-5xl appearance-none w-full py-4 px-3 text-gray-700 leading-tight outline-none" value={{ title }}/>
                         } else {
@@ -60,7 +60,7 @@ impl Template for RootTemplate {
                         </textarea>
                         <button type="submit" class="underline p-3 mt-3 text-xl text-gray-800">{"Save changes"}</button>
                       </form>
-                      <form action={{ &format!("/notes/{}", &note.id) }} method="delete">
+                      <form action={{ &routes::note_path(note.id) }} method="delete">
                         <button type="submit" class="underline p-3 mt-1 text-xl text-red-700">{"Delete"}</button>
                       </form>
                     } else {
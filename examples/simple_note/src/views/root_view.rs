@@ -1,5 +1,6 @@
 use crate::{templates::root_template::RootTemplate, view_models::root_view_model::RootViewModel};
 use rocal::rocal_core::traits::{SharedRouter, Template, View};
+#[derive(Clone)]
 pub struct RootView {
     router: SharedRouter,
 }
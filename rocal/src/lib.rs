@@ -5,5 +5,8 @@ pub use rocal_macro::action;
 pub use rocal_macro::config;
 pub use rocal_macro::main;
 pub use rocal_macro::migrate;
+pub use rocal_macro::query;
+pub use rocal_macro::query_as;
 pub use rocal_macro::route;
 pub use rocal_macro::view;
+pub use rocal_macro::Model;
@@ -1,18 +1,804 @@
 import sqlite3InitModule from './sqlite3.mjs';
 
 const dbCache = Object.create(null);
+const transactions = Object.create(null);
+const changedTables = Object.create(null);
+const sessions = Object.create(null);
+const sqlite3Ready = sqlite3InitModule();
 
-self.onmessage = function (message) {
-    const db_name = message.data.db;
+// Where databases are kept without OPFS, and the database which kvvfs keeps, given by the
+// first message.
+let storageFallback = "memory";
+let kvvfsDb = null;
+let post = (message) => self.postMessage(message);
 
-    self.sqlite3InitModule().then((sqlite3) => {
-	if (sqlite3.capi.sqlite3_vfs_find("opfs")) {
-	    const db = dbCache[db_name] ??= new sqlite3.oo1.OpfsDb(db_name, "ct");
-	    if (!!message.data.query) {
-		self.postMessage(db.exec(message.data.query, { bind: message.data.bindings, rowMode: 'object' }));
+if (typeof WorkerGlobalScope !== "undefined" && self instanceof WorkerGlobalScope) {
+    self.onmessage = (message) => receive(message.data);
+}
+
+// kvvfs keeps a database in localStorage or sessionStorage, which only the main thread can
+// reach, so global.js runs this module there instead of in a worker when kvvfs is needed.
+export function connect(postMessage) {
+    post = postMessage;
+}
+
+export function receive(data) {
+    sqlite3Ready.then((sqlite3) => {
+	if (data.storage_fallback !== undefined) {
+	    storageFallback = data.storage_fallback;
+	    kvvfsDb = data.kvvfs_db;
+	    post({ id: data.id, result: { opfs: !!sqlite3.capi.sqlite3_vfs_find("opfs") } });
+	    return;
+	}
+
+	const storage = storageOf(sqlite3, data.db);
+
+	if (data.action === "storage") {
+	    post({ id: data.id, result: storage });
+	} else if (storage === "none") {
+	    post({ id: data.id, error: { kind: "opfs_unavailable", message: "OPFS not available because of your browser capability." } });
+	} else {
+	    dispatch(sqlite3, data);
+	}
+    }).catch((err) => {
+	post({ id: data.id, error: { kind: "worker", message: err.message } });
+    });
+}
+
+// Databases are kept in OPFS whenever it's available. kvvfs has room for only one database in
+// each storage, so any other database is kept in memory.
+function storageOf(sqlite3, db_name) {
+    if (sqlite3.capi.sqlite3_vfs_find("opfs")) {
+	return "opfs";
+    }
+
+    if (storageFallback === "local_storage" || storageFallback === "session_storage") {
+	return db_name === kvvfsDb && sqlite3.oo1.JsStorageDb !== undefined ? storageFallback : "memory";
+    }
+
+    return storageFallback;
+}
+
+// While a transaction is open on a database, messages which don't belong to it wait
+// in a queue so that they never run in the middle of the transaction.
+function dispatch(sqlite3, data) {
+    const state = transactions[data.db] ??= { current: null, queue: [] };
+
+    // Finishing a transaction which isn't open, e.g. one rolled back when its handle was dropped,
+    // doesn't wait. One which is still waiting to begin is taken out of the queue.
+    if ((data.action === "commit" || data.action === "rollback") && state.current !== data.transaction) {
+	const begin = state.queue.findIndex((queued) => queued.action === "begin" && queued.transaction === data.transaction);
+
+	if (begin !== -1) {
+	    const [queued] = state.queue.splice(begin, 1);
+	    post({ id: queued.id, error: { kind: "transaction", message: "The transaction was rolled back before it began" } });
+	}
+
+	if (data.action === "rollback") {
+	    post({ id: data.id, result: null });
+	} else {
+	    post({ id: data.id, error: { kind: "transaction", message: "The transaction is not open" } });
+	}
+	return;
+    }
+
+    if (state.current !== null && state.current !== data.transaction) {
+	state.queue.push(data);
+	return;
+    }
+
+    handle(sqlite3, state, data);
+    drain(sqlite3, state);
+}
+
+function endTransaction(sqlite3, state, db_name) {
+    state.current = null;
+
+    if (state.untracked) {
+	sqlite3.capi.sqlite3session_enable(sessions[db_name], 1);
+	state.untracked = false;
+    }
+}
+
+function drain(sqlite3, state) {
+    while (state.current === null && 0 < state.queue.length) {
+	handle(sqlite3, state, state.queue.shift());
+    }
+}
+
+// Holds the queue of a database like a transaction while its file is replaced.
+const IMPORT = Symbol("import");
+
+function handle(sqlite3, state, data) {
+    const { id, db: db_name, action, query, bindings, transaction, limit, column } = data;
+
+    try {
+	const db = dbCache[db_name] ??= open(sqlite3, db_name);
+
+	if (action === "begin") {
+	    db.exec("begin immediate;");
+	    state.current = transaction;
+
+	    // Writes of migrations aren't recorded as changes since every device runs them by itself,
+	    // e.g. rows which a migration seeds would conflict on the other devices otherwise.
+	    if (data.untracked && sessions[db_name] !== undefined) {
+		sqlite3.capi.sqlite3session_enable(sessions[db_name], 0);
+		state.untracked = true;
+	    }
+
+	    post({ id, result: transaction });
+	} else if (action === "commit") {
+	    endTransaction(sqlite3, state, db_name);
+	    try {
+		db.exec("commit;");
+	    } catch (err) {
+		rollback(db);
+		changedTables[db_name].clear();
+		throw err;
 	    }
+	    recordChanges(sqlite3, db, db_name);
+	    post({ id, result: null });
+	    notifyChanges(db_name);
+	} else if (action === "rollback") {
+	    endTransaction(sqlite3, state, db_name);
+	    rollback(db);
+	    changedTables[db_name].clear();
+	    resetSession(sqlite3, db, db_name);
+	    post({ id, result: null });
+	} else if (action === "tables") {
+	    post({ id, result: readTables(sqlite3, db, db_name, query) });
+	} else if (action === "track_changes") {
+	    trackChanges(sqlite3, db, db_name);
+	    post({ id, result: null });
+	} else if (action === "sync_state") {
+	    post({ id, result: getSyncState(db) });
+	} else if (action === "set_cursor") {
+	    db.exec({ sql: "insert or replace into _rocal_sync_state (key, value) values ('cursor', ?);", bind: [String(data.cursor)] });
+	    post({ id, result: null });
+	} else if (action === "pending_changesets") {
+	    post({ id, result: getPendingChangesets(sqlite3, db) });
+	} else if (action === "mark_pushed") {
+	    db.exec({ sql: "delete from _rocal_changesets where id <= ?;", bind: [data.up_to] });
+	    post({ id, result: null });
+	} else if (action === "export") {
+	    post({ id, result: sqlite3.capi.sqlite3_js_db_export(db) });
+	} else if (action === "import" || action === "replace") {
+	    state.current = IMPORT;
+
+	    const replacing = action === "import"
+		? importDatabase(sqlite3, db, db_name, data.bytes, data.migrations)
+		: replaceDatabase(sqlite3, db, db_name, data.bytes);
+
+	    replacing
+		.then(() => post({ id, result: null }))
+		.catch((err) => post({ id, error: toError(err) }))
+		.finally(() => {
+		    state.current = null;
+		    drain(sqlite3, state);
+		});
+	} else if (action === "apply_changesets") {
+	    post({ id, result: applyChangesets(sqlite3, db, db_name, data.changesets, data.decisions) });
 	} else {
-	    console.error("OPFS not available because of your browser capability.");
+	    const result = !!query ? select(db, query, bindings, limit, column) : [];
+
+	    if (state.current === null) {
+		recordChanges(sqlite3, db, db_name);
+	    }
+
+	    post({ id, result });
+
+	    if (state.current === null) {
+		notifyChanges(db_name);
+	    }
+	}
+    } catch (err) {
+	post({ id, error: toError(err) });
+    }
+}
+
+function toError(err) {
+    return err.kind !== undefined ? err : { kind: "sqlite", code: err.resultCode ?? null, message: err.message };
+}
+
+// Records every table which a write touches so that live queries on it can be refreshed.
+function open(sqlite3, db_name, bytes) {
+    const db = openStorage(sqlite3, db_name, bytes);
+    const tables = changedTables[db_name] = new Set();
+
+    sqlite3.capi.sqlite3_update_hook(db, (_ctx, _op, _db, table, _rowid) => {
+	if (!table.startsWith("_rocal_")) {
+	    tables.add(table);
+	}
+    }, 0);
+
+    authorize(sqlite3, db, tables, null);
+
+    // Changes are tracked from the start once changeset sync has been turned on for the database.
+    if (db.selectValue("select count(*) from sqlite_master where type = 'table' and name = '_rocal_changesets';") > 0) {
+	sessions[db_name] = createSession(sqlite3, db);
+    }
+
+    return db;
+}
+
+// A database kept in memory starts from `bytes` if they're given.
+function openStorage(sqlite3, db_name, bytes) {
+    switch (storageOf(sqlite3, db_name)) {
+    case "opfs":
+	return new sqlite3.oo1.OpfsDb(db_name, "ct");
+    case "local_storage":
+	return new sqlite3.oo1.JsStorageDb("local");
+    case "session_storage":
+	return new sqlite3.oo1.JsStorageDb("session");
+    default: {
+	const db = new sqlite3.oo1.DB(":memory:", "ct");
+
+	if (bytes !== undefined) {
+	    deserialize(sqlite3, db, bytes);
+	}
+
+	return db;
+    }
+    }
+}
+
+function deserialize(sqlite3, db, bytes) {
+    const { capi, wasm } = sqlite3;
+    const pData = wasm.allocFromTypedArray(bytes);
+
+    db.checkRc(capi.sqlite3_deserialize(db, "main", pData, bytes.length, bytes.length, capi.SQLITE_DESERIALIZE_FREEONCLOSE | capi.SQLITE_DESERIALIZE_RESIZEABLE));
+}
+
+const SQLITE_HEADER = new TextEncoder().encode("SQLite format 3\0");
+
+// Replaces the file of a database with `bytes`. The file is checked and given the missing
+// `migrations` in memory first, so that the database is left as it was if the file is broken or
+// still lacks a migration which the database has.
+async function importDatabase(sqlite3, db, db_name, bytes, migrations) {
+    if (bytes.length < 100 || !SQLITE_HEADER.every((byte, i) => bytes[i] === byte)) {
+	throw { kind: "not_a_database", message: "The file is not a SQLite database." };
+    }
+
+    const copy = new sqlite3.oo1.DB(":memory:", "ct");
+
+    try {
+	try {
+	    deserialize(sqlite3, copy, bytes);
+	    if (copy.selectValue("pragma quick_check;") !== "ok") {
+		throw new Error("The file is corrupted.");
+	    }
+	} catch (_) {
+	    throw { kind: "not_a_database", message: "The file is not a SQLite database." };
+	}
+
+	migrate(copy, migrations);
+
+	const applied = appliedVersions(copy);
+	const missing = appliedVersions(db).filter((version) => !applied.includes(version));
+
+	if (missing.length > 0) {
+	    throw { kind: "missing_migrations", message: missing.join(", ") };
+	}
+
+	bytes = sqlite3.capi.sqlite3_js_db_export(copy);
+    } finally {
+	copy.close();
+    }
+
+    await replaceDatabase(sqlite3, db, db_name, bytes);
+}
+
+// Replaces the file of a database with `bytes` while its connection is closed, e.g. with a
+// snapshot pulled by the sync worker, which would otherwise write the file under the open
+// connection. The previous file is restored if the new one can't be written. Every table counts
+// as changed afterwards so that live queries are refreshed.
+async function replaceDatabase(sqlite3, db, db_name, bytes) {
+    const previous = sqlite3.capi.sqlite3_js_db_export(db);
+
+    close(sqlite3, db_name);
+
+    try {
+	await writeStorage(sqlite3, db_name, bytes);
+    } catch (err) {
+	await writeStorage(sqlite3, db_name, previous);
+	dbCache[db_name] = open(sqlite3, db_name, previous);
+	throw err;
+    }
+
+    const imported = dbCache[db_name] = open(sqlite3, db_name, bytes);
+    const tables = imported.exec("select name from sqlite_master where type = 'table';", { rowMode: 0, returnValue: 'resultRows' });
+
+    for (const table of tables) {
+	if (!table.startsWith("_rocal_") && !table.startsWith("sqlite_")) {
+	    changedTables[db_name].add(table);
+	}
+    }
+
+    notifyChanges(db_name);
+}
+
+// A database kept in memory is written when it's opened with the bytes instead.
+async function writeStorage(sqlite3, db_name, bytes) {
+    const storage = storageOf(sqlite3, db_name);
+
+    if (storage === "opfs") {
+	await sqlite3.oo1.OpfsDb.importDb(db_name, bytes);
+    } else if (storage === "local_storage" || storage === "session_storage") {
+	const source = new sqlite3.oo1.DB(":memory:", "ct");
+
+	try {
+	    deserialize(sqlite3, source, bytes);
+	    sqlite3.oo1.JsStorageDb.clearStorage(storage === "local_storage" ? "local" : "session");
+	    source.exec(`vacuum into 'file:${storage === "local_storage" ? "local" : "session"}?vfs=kvvfs';`);
+	} finally {
+	    source.close();
+	}
+    }
+}
+
+// Applies the migrations which a database doesn't have yet like `Database::migrate` does.
+function migrate(db, migrations) {
+    if (migrations.length === 0) {
+	return;
+    }
+
+    db.exec("create table if not exists schema_migrations (version text primary key, applied_at datetime default current_timestamp);");
+
+    const applied = appliedVersions(db);
+
+    for (const [version, query] of migrations) {
+	if (!applied.includes(version)) {
+	    db.transaction(() => {
+		db.exec(query);
+		db.exec({ sql: "insert into schema_migrations (version) values (?);", bind: [version] });
+	    });
+	}
+    }
+}
+
+function appliedVersions(db) {
+    if (db.selectValue("select count(*) from sqlite_master where type = 'table' and name = 'schema_migrations';") === 0) {
+	return [];
+    }
+
+    return db.selectValues("select version from schema_migrations;");
+}
+
+function close(sqlite3, db_name) {
+    if (sessions[db_name] !== undefined) {
+	sqlite3.capi.sqlite3session_delete(sessions[db_name]);
+	delete sessions[db_name];
+    }
+
+    dbCache[db_name]?.close();
+    delete dbCache[db_name];
+}
+
+function notifyChanges(db_name) {
+    const tables = changedTables[db_name];
+
+    if (tables === undefined || tables.size === 0) {
+	return;
+    }
+
+    post({ changes: { db: db_name, tables: [...tables] } });
+    tables.clear();
+}
+
+// The authorizer is called for each table which a statement touches while it's prepared. The
+// tables which it writes are added to `written`, since the update hook misses a `delete` without
+// `where`, which SQLite runs by emptying the table. With `read`, the statement is only prepared
+// to see the tables which it reads.
+function authorize(sqlite3, db, written, read) {
+    const { SQLITE_READ, SQLITE_INSERT, SQLITE_UPDATE, SQLITE_DELETE, SQLITE_OK } = sqlite3.capi;
+
+    sqlite3.capi.sqlite3_set_authorizer(db, (_ctx, code, table, _column, _db, _trigger) => {
+	if (typeof table !== "string" || table.startsWith("sqlite_") || table.startsWith("_rocal_")) {
+	    return SQLITE_OK;
+	}
+
+	if (read !== null) {
+	    if (code === SQLITE_READ) {
+		read.add(table);
+	    }
+	} else if (code === SQLITE_INSERT || code === SQLITE_UPDATE || code === SQLITE_DELETE) {
+	    written.add(table);
+	}
+
+	return SQLITE_OK;
+    }, 0);
+}
+
+// Returns the tables which a query reads.
+function readTables(sqlite3, db, db_name, query) {
+    const tables = new Set();
+
+    authorize(sqlite3, db, null, tables);
+
+    try {
+	db.prepare(query).finalize();
+    } finally {
+	authorize(sqlite3, db, changedTables[db_name], null);
+    }
+
+    return [...tables];
+}
+
+// Tables which are never synced because they only make sense on this device.
+const UNTRACKED_TABLES = ["schema_migrations", "sync_connections"];
+
+function isTracked(table) {
+    return !table.startsWith("_rocal_") && !table.startsWith("sqlite_") && !UNTRACKED_TABLES.includes(table);
+}
+
+function trackChanges(sqlite3, db, db_name) {
+    if (sessions[db_name] !== undefined) {
+	return;
+    }
+
+    db.exec(`
+      create table if not exists _rocal_changesets (id integer primary key autoincrement, changeset blob not null, created_at datetime default current_timestamp);
+      create table if not exists _rocal_sync_state (key text primary key, value text not null);
+    `);
+    db.exec({ sql: "insert or ignore into _rocal_sync_state (key, value) values ('device_id', ?);", bind: [crypto.randomUUID()] });
+
+    sessions[db_name] = createSession(sqlite3, db);
+}
+
+function createSession(sqlite3, db) {
+    const { capi, wasm } = sqlite3;
+    const stack = wasm.pstack.pointer;
+
+    try {
+	const ppSession = wasm.pstack.allocPtr();
+	db.checkRc(capi.sqlite3session_create(db, "main", ppSession));
+
+	const pSession = wasm.peekPtr(ppSession);
+	capi.sqlite3session_table_filter(pSession, (_ctx, table) => isTracked(table) ? 1 : 0, 0);
+	db.checkRc(capi.sqlite3session_attach(pSession, null));
+
+	return pSession;
+    } finally {
+	wasm.pstack.restore(stack);
+    }
+}
+
+function resetSession(sqlite3, db, db_name) {
+    if (sessions[db_name] === undefined) {
+	return;
+    }
+
+    sqlite3.capi.sqlite3session_delete(sessions[db_name]);
+    sessions[db_name] = createSession(sqlite3, db);
+}
+
+// Moves what the session has captured since the last commit into the change log, which
+// survives reloads until the changes are pushed.
+function recordChanges(sqlite3, db, db_name) {
+    const pSession = sessions[db_name];
+
+    if (pSession === undefined || sqlite3.capi.sqlite3session_isempty(pSession)) {
+	return;
+    }
+
+    const { capi, wasm } = sqlite3;
+    const stack = wasm.pstack.pointer;
+    let changeset;
+
+    try {
+	const pnChangeset = wasm.pstack.allocPtr();
+	const ppChangeset = wasm.pstack.allocPtr();
+	db.checkRc(capi.sqlite3session_changeset(pSession, pnChangeset, ppChangeset));
+
+	const pChangeset = wasm.peekPtr(ppChangeset);
+	changeset = wasm.heap8u().slice(pChangeset, pChangeset + wasm.peek32(pnChangeset));
+	capi.sqlite3_free(pChangeset);
+    } finally {
+	wasm.pstack.restore(stack);
+    }
+
+    resetSession(sqlite3, db, db_name);
+
+    if (0 < changeset.length) {
+	db.exec({ sql: "insert into _rocal_changesets (changeset) values (?);", bind: [changeset] });
+    }
+}
+
+function getSyncState(db) {
+    const state = { device_id: null, cursor: null };
+
+    db.exec("select key, value from _rocal_sync_state;", {
+	rowMode: 'array',
+	callback: ([key, value]) => {
+	    state[key] = value;
 	}
     });
+
+    return state;
+}
+
+// Combines every change which hasn't been pushed yet into one changeset.
+function getPendingChangesets(sqlite3, db) {
+    const rows = db.exec("select id, changeset from _rocal_changesets order by id asc;", { rowMode: 'array', returnValue: 'resultRows' });
+
+    if (rows.length === 0) {
+	return null;
+    }
+
+    return { up_to: rows[rows.length - 1][0], changeset: combineChangesets(sqlite3, db, rows.map(([_, changeset]) => changeset)) };
+}
+
+function combineChangesets(sqlite3, db, changesets) {
+    const { capi, wasm } = sqlite3;
+    const stack = wasm.pstack.pointer;
+    let pGroup = 0;
+
+    try {
+	const ppGroup = wasm.pstack.allocPtr();
+	db.checkRc(capi.sqlite3changegroup_new(ppGroup));
+	pGroup = wasm.peekPtr(ppGroup);
+
+	for (const changeset of changesets) {
+	    const pData = wasm.allocFromTypedArray(changeset);
+	    try {
+		db.checkRc(capi.sqlite3changegroup_add(pGroup, changeset.length, pData));
+	    } finally {
+		wasm.dealloc(pData);
+	    }
+	}
+
+	const pnChangeset = wasm.pstack.allocPtr();
+	const ppChangeset = wasm.pstack.allocPtr();
+	db.checkRc(capi.sqlite3changegroup_output(pGroup, pnChangeset, ppChangeset));
+
+	const pChangeset = wasm.peekPtr(ppChangeset);
+	const result = wasm.heap8u().slice(pChangeset, pChangeset + wasm.peek32(pnChangeset));
+	capi.sqlite3_free(pChangeset);
+
+	return result;
+    } finally {
+	if (pGroup) {
+	    capi.sqlite3changegroup_delete(pGroup);
+	}
+	wasm.pstack.restore(stack);
+    }
+}
+
+// Applies changesets pulled from other devices. Without `decisions` it only collects the
+// conflicts, leaving the database as it was, so that the app can decide how to resolve them.
+function applyChangesets(sqlite3, db, db_name, changesets, decisions) {
+    const { capi, wasm } = sqlite3;
+
+    if (changesets.length === 0) {
+	return [];
+    }
+
+    const changeset = combineChangesets(sqlite3, db, changesets);
+    const pendingKeys = getPendingKeys(sqlite3, db);
+    const resolutions = new Map((decisions ?? []).map(({ key, resolution }) => [key, resolution]));
+    const conflicts = [];
+    const pSession = sessions[db_name];
+
+    // Changes from other devices are not recorded again as local changes.
+    if (pSession !== undefined) {
+	capi.sqlite3session_enable(pSession, 0);
+    }
+
+    db.exec("savepoint _rocal_apply;");
+
+    const pData = wasm.allocFromTypedArray(changeset);
+    let failure = null;
+
+    try {
+	const rc = capi.sqlite3changeset_apply(db, changeset.length, pData, 0, (_ctx, eConflict, pIter) => {
+	    try {
+		const conflict = describeConflict(sqlite3, db, eConflict, pIter);
+		conflict.pending_local = pendingKeys.has(conflict.key);
+
+		if (decisions === undefined) {
+		    conflicts.push(conflict);
+		    return capi.SQLITE_CHANGESET_OMIT;
+		}
+
+		return resolveConflict(sqlite3, conflict, resolutions.get(conflict.key));
+	    } catch (err) {
+		failure = err;
+		return capi.SQLITE_CHANGESET_ABORT;
+	    }
+	}, 0);
+
+	if (failure !== null) {
+	    throw failure;
+	}
+
+	db.checkRc(rc);
+
+	if (decisions === undefined) {
+	    db.exec("rollback to _rocal_apply;");
+	}
+
+	db.exec("release _rocal_apply;");
+    } catch (err) {
+	db.exec("rollback to _rocal_apply; release _rocal_apply;");
+	throw err;
+    } finally {
+	wasm.dealloc(pData);
+
+	if (pSession !== undefined) {
+	    capi.sqlite3session_enable(pSession, 1);
+	}
+    }
+
+    return conflicts;
+}
+
+// Without a decision from the app, a row which also has a change waiting to be pushed keeps
+// the local version, which is pushed afterwards, and any other row takes the remote version.
+function resolveConflict(sqlite3, conflict, resolution) {
+    const { capi } = sqlite3;
+
+    resolution ??= conflict.pending_local ? "local" : "remote";
+
+    if (resolution === "abort") {
+	return capi.SQLITE_CHANGESET_ABORT;
+    }
+
+    // Only a conflicting row can be replaced; a missing row or a broken constraint is skipped.
+    if (resolution === "remote" && (conflict.kind === "data" || conflict.kind === "conflict")) {
+	return capi.SQLITE_CHANGESET_REPLACE;
+    }
+
+    return capi.SQLITE_CHANGESET_OMIT;
+}
+
+const CONFLICT_KINDS = {
+    1: "data",
+    2: "not_found",
+    3: "conflict",
+    4: "constraint",
+    5: "foreign_key",
 };
+
+function describeConflict(sqlite3, db, eConflict, pIter) {
+    const { capi, wasm } = sqlite3;
+    const stack = wasm.pstack.pointer;
+
+    try {
+	const pzTable = wasm.pstack.allocPtr();
+	const pnColumn = wasm.pstack.allocPtr();
+	const pOp = wasm.pstack.allocPtr();
+	const pbIndirect = wasm.pstack.allocPtr();
+	db.checkRc(capi.sqlite3changeset_op(pIter, pzTable, pnColumn, pOp, pbIndirect));
+
+	const table = wasm.cstrToJs(wasm.peekPtr(pzTable));
+	const nColumn = wasm.peek32(pnColumn);
+	const op = wasm.peek32(pOp);
+	const kind = CONFLICT_KINDS[eConflict] ?? "constraint";
+
+	const columns = db.exec(`select name from pragma_table_info(?) order by cid;`, { bind: [table], rowMode: 0, returnValue: 'resultRows' });
+	const read = (fn) => Array.from({ length: nColumn }, (_, i) => fn(pIter, i) ?? null);
+
+	const old = op === capi.SQLITE_INSERT ? null : read(capi.sqlite3changeset_old_js);
+	const remote = op === capi.SQLITE_DELETE ? null : read(capi.sqlite3changeset_new_js);
+	const local = kind === "data" || kind === "conflict" ? read((pIter, i) => conflictValue(sqlite3, pIter, i)) : null;
+
+	return {
+	    key: rowKey(sqlite3, db, pIter, table, op),
+	    table,
+	    kind,
+	    operation: op === capi.SQLITE_INSERT ? "insert" : op === capi.SQLITE_UPDATE ? "update" : "delete",
+	    columns,
+	    old,
+	    remote,
+	    local,
+	};
+    } finally {
+	wasm.pstack.restore(stack);
+    }
+}
+
+function conflictValue(sqlite3, pIter, i) {
+    const { capi, wasm } = sqlite3;
+    const stack = wasm.pstack.pointer;
+
+    try {
+	const ppValue = wasm.pstack.allocPtr();
+	capi.sqlite3changeset_conflict(pIter, i, ppValue);
+	const pValue = wasm.peekPtr(ppValue);
+	return pValue ? capi.sqlite3_value_to_js(pValue, true) : null;
+    } finally {
+	wasm.pstack.restore(stack);
+    }
+}
+
+// Identifies a row by its table and primary key so that changes to the same row can be matched.
+function rowKey(sqlite3, db, pIter, table, op) {
+    const { capi, wasm } = sqlite3;
+    const stack = wasm.pstack.pointer;
+
+    try {
+	const pabPK = wasm.pstack.allocPtr();
+	const pnColumn = wasm.pstack.allocPtr();
+	db.checkRc(capi.sqlite3changeset_pk(pIter, pabPK, pnColumn));
+
+	const abPK = wasm.peekPtr(pabPK);
+	const nColumn = wasm.peek32(pnColumn);
+	const values = [];
+
+	for (let i = 0; i < nColumn; i++) {
+	    if (wasm.peek8(abPK + i)) {
+		values.push(op === capi.SQLITE_INSERT ? capi.sqlite3changeset_new_js(pIter, i) : capi.sqlite3changeset_old_js(pIter, i));
+	    }
+	}
+
+	return `${table}:${JSON.stringify(values, (_, value) => typeof value === "bigint" ? value.toString() : value)}`;
+    } finally {
+	wasm.pstack.restore(stack);
+    }
+}
+
+function getPendingKeys(sqlite3, db) {
+    const pending = getPendingChangesets(sqlite3, db);
+    const keys = new Set();
+
+    if (pending === null) {
+	return keys;
+    }
+
+    const { capi, wasm } = sqlite3;
+    const stack = wasm.pstack.pointer;
+    const pData = wasm.allocFromTypedArray(pending.changeset);
+    let pIter = 0;
+
+    try {
+	const ppIter = wasm.pstack.allocPtr();
+	db.checkRc(capi.sqlite3changeset_start(ppIter, pending.changeset.length, pData));
+	pIter = wasm.peekPtr(ppIter);
+
+	const pzTable = wasm.pstack.allocPtr();
+	const pnColumn = wasm.pstack.allocPtr();
+	const pOp = wasm.pstack.allocPtr();
+	const pbIndirect = wasm.pstack.allocPtr();
+
+	while (capi.sqlite3changeset_next(pIter) === capi.SQLITE_ROW) {
+	    db.checkRc(capi.sqlite3changeset_op(pIter, pzTable, pnColumn, pOp, pbIndirect));
+	    keys.add(rowKey(sqlite3, db, pIter, wasm.cstrToJs(wasm.peekPtr(pzTable)), wasm.peek32(pOp)));
+	}
+    } finally {
+	if (pIter) {
+	    capi.sqlite3changeset_finalize(pIter);
+	}
+	wasm.dealloc(pData);
+	wasm.pstack.restore(stack);
+    }
+
+    return keys;
+}
+
+// Stops stepping through the result set once `limit` rows are collected, and returns
+// only the value of `column` for each row if it is given.
+function select(db, query, bindings, limit, column) {
+    const rows = [];
+
+    db.exec(query, {
+	bind: bindings,
+	rowMode: column ?? 'object',
+	callback: (row) => {
+	    rows.push(row);
+
+	    if (limit !== undefined && limit <= rows.length) {
+		return false;
+	    }
+	}
+    });
+
+    return rows;
+}
+
+function rollback(db) {
+    try {
+	db.exec("rollback;");
+    } catch {
+	// SQLite may have already rolled the transaction back by itself.
+    }
+}
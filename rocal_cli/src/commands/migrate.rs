@@ -1,8 +1,16 @@
-use std::fs::File;
+use std::fs;
 
 use chrono::Utc;
+use rocal_core::migration_file::{DOWN_MARKER, UP_MARKER};
+use rusqlite::Connection;
 
-use crate::commands::utils::project::find_project_root;
+use crate::{
+    commands::utils::{color::Color, project::find_project_root},
+    migrations::{
+        apply_migration, get_last_applied, get_migrations, get_status, open_database,
+        roll_back_migration, Migration, Status,
+    },
+};
 
 pub fn add(name: &str) {
     let now = Utc::now();
@@ -11,8 +19,117 @@ pub fn add(name: &str) {
 
     let root_path = find_project_root().expect("Failed to find the project root");
 
-    File::create(root_path.join(&format!("db/migrations/{file_name}")))
-        .expect(&format!("Failed to create db/migrations/{file_name}"));
+    fs::write(
+        root_path.join(format!("db/migrations/{file_name}")),
+        format!("{UP_MARKER}\n\n\n{DOWN_MARKER}\n\n"),
+    )
+    .unwrap_or_else(|_| panic!("Failed to create db/migrations/{file_name}"));
 
     println!("{file_name}");
 }
+
+pub fn status(database: &str) {
+    let (conn, migrations) = match open(database, true) {
+        Ok(opened) => opened,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+
+    let status = match get_status(&conn, &migrations) {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+
+    for (status, name) in status {
+        match status {
+            Status::Up => println!("{}  {}", Color::Green.text("up  "), name),
+            Status::Down => println!("{}  {}", Color::Yellow.text("down"), name),
+            Status::FileNotFound => {
+                println!("{}  {} (file not found)", Color::Red.text("up  "), name)
+            }
+        }
+    }
+}
+
+pub fn rollback(database: &str, steps: usize) {
+    let (mut conn, migrations) = match open(database, false) {
+        Ok(opened) => opened,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+
+    let last_applied = match get_last_applied(&conn, &migrations, steps) {
+        Ok(last_applied) => last_applied,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+
+    if last_applied.is_empty() {
+        println!("No migrations to roll back");
+        return;
+    }
+
+    for migration in last_applied {
+        if let Err(err) = roll_back_migration(&mut conn, migration) {
+            eprintln!("Failed to roll back {}: {}", migration.get_file_name(), err);
+            return;
+        }
+
+        println!("Rolled back {}", migration.get_file_name());
+    }
+}
+
+pub fn redo(database: &str) {
+    let (mut conn, migrations) = match open(database, false) {
+        Ok(opened) => opened,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+
+    let migration: &Migration = match get_last_applied(&conn, &migrations, 1) {
+        Ok(last_applied) => match last_applied.first() {
+            Some(migration) => migration,
+            None => {
+                println!("No migrations to redo");
+                return;
+            }
+        },
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+
+    if let Err(err) = roll_back_migration(&mut conn, migration) {
+        eprintln!("Failed to roll back {}: {}", migration.get_file_name(), err);
+        return;
+    }
+
+    println!("Rolled back {}", migration.get_file_name());
+
+    if let Err(err) = apply_migration(&mut conn, migration) {
+        eprintln!("Failed to apply {}: {}", migration.get_file_name(), err);
+        return;
+    }
+
+    println!("Applied {}", migration.get_file_name());
+}
+
+fn open(database: &str, read_only: bool) -> Result<(Connection, Vec<Migration>), String> {
+    let root_path = find_project_root().expect("Failed to find the project root");
+    let migrations = get_migrations(&root_path.join("db/migrations"))?;
+    let conn = open_database(&root_path.join(database), read_only)?;
+
+    Ok((conn, migrations))
+}
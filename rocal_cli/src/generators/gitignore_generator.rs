@@ -7,6 +7,7 @@ pkg
 release
 release.tar.gz
 Cargo.lock
+db/*.sqlite3
 "#;
 
     let mut file = File::create(".gitignore").expect("Failed to create .gitignore");
@@ -2,6 +2,7 @@
 
 mod commands;
 mod generators;
+pub mod migrations;
 mod response;
 mod rocal_api_client;
 mod runner;
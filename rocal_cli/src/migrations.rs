@@ -0,0 +1,166 @@
+use std::path::Path;
+
+pub use rocal_core::migration_file::MigrationFile as Migration;
+use rocal_core::migration_file::{read_migration_files, DOWN_MARKER};
+use rusqlite::{Connection, OpenFlags};
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Up,
+    Down,
+    /// Applied to the database, but its file isn't in the migrations directory anymore.
+    FileNotFound,
+}
+
+/// Reads the migrations in a directory in the order of their versions, failing on the same
+/// files as `migrate!` does.
+pub fn get_migrations(dir: &Path) -> Result<Vec<Migration>, String> {
+    read_migration_files(dir)
+}
+
+/// Opens a copy of an app's database. A `read_only` one is left as it is, even without a
+/// `schema_migrations` table.
+pub fn open_database(path: &Path, read_only: bool) -> Result<Connection, String> {
+    if !path.exists() {
+        return Err(format!(
+            "{} not found. Put a copy of your app's database there or pass --database",
+            path.display()
+        ));
+    }
+
+    let flags = if read_only {
+        OpenFlags::SQLITE_OPEN_READ_ONLY
+    } else {
+        OpenFlags::SQLITE_OPEN_READ_WRITE
+    };
+
+    Connection::open_with_flags(path, flags).map_err(|err| err.to_string())
+}
+
+/// Returns the versions applied to a database in order, which are none before `schema_migrations`
+/// is created by the first migration.
+pub fn get_applied_versions(conn: &Connection) -> Result<Vec<String>, String> {
+    let exists: i64 = conn
+        .query_row(
+            "select count(*) from sqlite_master where type = 'table' and name = 'schema_migrations';",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|err| err.to_string())?;
+
+    if exists == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut stmt = conn
+        .prepare("select version from schema_migrations order by version asc;")
+        .map_err(|err| err.to_string())?;
+
+    let versions = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|err| err.to_string())?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(versions)
+}
+
+/// Returns the status of each migration by its file name, followed by the applied versions whose
+/// files aren't found.
+pub fn get_status(
+    conn: &Connection,
+    migrations: &[Migration],
+) -> Result<Vec<(Status, String)>, String> {
+    let applied = get_applied_versions(conn)?;
+
+    let mut status: Vec<(Status, String)> = migrations
+        .iter()
+        .map(|migration| {
+            if applied
+                .iter()
+                .any(|version| version == migration.get_version())
+            {
+                (Status::Up, migration.get_file_name().to_string())
+            } else {
+                (Status::Down, migration.get_file_name().to_string())
+            }
+        })
+        .collect();
+
+    for version in applied
+        .into_iter()
+        .filter(|version| !migrations.iter().any(|m| &m.get_version() == version))
+    {
+        status.push((Status::FileNotFound, version));
+    }
+
+    Ok(status)
+}
+
+/// Returns the last `steps` migrations applied to a database, the newest first.
+pub fn get_last_applied<'a>(
+    conn: &Connection,
+    migrations: &'a [Migration],
+    steps: usize,
+) -> Result<Vec<&'a Migration>, String> {
+    get_applied_versions(conn)?
+        .iter()
+        .rev()
+        .take(steps)
+        .map(|version| {
+            migrations
+                .iter()
+                .find(|m| &m.get_version() == version)
+                .ok_or_else(|| format!("Migration {} is not found in db/migrations", version))
+        })
+        .collect()
+}
+
+pub fn apply_migration(conn: &mut Connection, migration: &Migration) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|err| err.to_string())?;
+
+    tx.execute_batch(
+        "create table if not exists schema_migrations (version text primary key, applied_at datetime default current_timestamp);",
+    )
+    .map_err(|err| err.to_string())?;
+    tx.execute_batch(migration.get_up())
+        .map_err(|err| err.to_string())?;
+    tx.execute(
+        "insert into schema_migrations (version) values (?1);",
+        [migration.get_version()],
+    )
+    .map_err(|err| err.to_string())?;
+
+    tx.commit().map_err(|err| err.to_string())
+}
+
+pub fn roll_back_migration(conn: &mut Connection, migration: &Migration) -> Result<(), String> {
+    let down = match migration.get_down() {
+        Some(down) if !down.trim().is_empty() => down,
+        Some(_) => {
+            return Err(format!(
+                "{} has an empty {} section",
+                migration.get_file_name(),
+                DOWN_MARKER
+            ))
+        }
+        None => {
+            return Err(format!(
+                "{} has no {} section",
+                migration.get_file_name(),
+                DOWN_MARKER
+            ))
+        }
+    };
+
+    let tx = conn.transaction().map_err(|err| err.to_string())?;
+
+    tx.execute_batch(down).map_err(|err| err.to_string())?;
+    tx.execute(
+        "delete from schema_migrations where version = ?1;",
+        [migration.get_version()],
+    )
+    .map_err(|err| err.to_string())?;
+
+    tx.commit().map_err(|err| err.to_string())
+}
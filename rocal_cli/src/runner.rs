@@ -1,4 +1,4 @@
-use clap::{builder::Str, command, Arg, Command, Id};
+use clap::{builder::Str, command, value_parser, Arg, ArgAction, ArgMatches, Command, Id};
 
 use crate::commands::{
     build::build, init::init, login::login, migrate, password, publish::publish,
@@ -58,6 +58,43 @@ pub async fn run() {
                 .about("Manage sync servers")
                 .arg_required_else_help(true)
                 .subcommand(Command::new(SyncServersSubcommand::List).about("List available sync servers and show app_id"))
+                .subcommand(
+                    Command::new(SyncServersSubcommand::Serve)
+                        .about("Run a sync server on your local which stores synced databases in a directory")
+                        .arg(
+                            Arg::new(SyncServersCommandArg::Port)
+                                .short('p')
+                                .long("port")
+                                .required(false)
+                                .help("Set port where you want to serve a sync server. A sync server runs on 4000 by default")
+                        )
+                        .arg(
+                            Arg::new(SyncServersCommandArg::Host)
+                                .long("host")
+                                .required(false)
+                                .help("Set an address to listen on, e.g. 0.0.0.0 to be reachable from other machines. 127.0.0.1 by default")
+                        )
+                        .arg(
+                            Arg::new(SyncServersCommandArg::PublicUrl)
+                                .long("public-url")
+                                .required(false)
+                                .help("Set a URL where apps reach the sync server, e.g. https://sync.example.com. http://<host>:<port> by default")
+                        )
+                        .arg(
+                            Arg::new(SyncServersCommandArg::Directory)
+                                .short('d')
+                                .long("directory")
+                                .required(false)
+                                .help("Set a directory to store synced databases. db/sync by default")
+                        )
+                        .arg(
+                            Arg::new(SyncServersCommandArg::AllowOrigin)
+                                .long("allow-origin")
+                                .required(false)
+                                .action(ArgAction::Append)
+                                .help("Allow an app served from an origin to sync, e.g. http://127.0.0.1:8080. The origins of `rocal run` on port 3000 by default")
+                        )
+                )
         )
         .subcommand(
             Command::new(Subcommand::Migrate)
@@ -68,6 +105,30 @@ pub async fn run() {
                         .about("Add a new migration file. e.g. db/migrations/<timestamp>-<name>.sql")
                         .arg(Arg::new("name").required(true))
                 )
+                .subcommand(
+                    Command::new(MigrateSubcommand::Status)
+                        .about("Show which migrations have been applied to a local copy of the database")
+                        .arg(database_arg())
+                )
+                .subcommand(
+                    Command::new(MigrateSubcommand::Rollback)
+                        .about("Roll back the latest migrations on a local copy of the database")
+                        .arg(database_arg())
+                        .arg(
+                            Arg::new(MigrateCommandArg::Steps)
+                                .short('s')
+                                .long("steps")
+                                .required(false)
+                                .default_value("1")
+                                .value_parser(value_parser!(usize))
+                                .help("Set how many migrations to roll back")
+                        )
+                )
+                .subcommand(
+                    Command::new(MigrateSubcommand::Redo)
+                        .about("Roll back and re-apply the latest migration on a local copy of the database")
+                        .arg(database_arg())
+                )
         )
         .about("A tool to create and build a Rocal app.")
         .arg_required_else_help(true)
@@ -110,15 +171,43 @@ pub async fn run() {
                                 .get_one::<String>("name")
                                 .expect("required argument");
                             migrate::add(&name);
+                        } else if name == MigrateSubcommand::Status.as_str() {
+                            migrate::status(get_database(arg_matches));
+                        } else if name == MigrateSubcommand::Rollback.as_str() {
+                            let steps = arg_matches
+                                .get_one::<usize>(MigrateCommandArg::Steps.as_str())
+                                .expect("default value");
+                            migrate::rollback(get_database(arg_matches), *steps);
+                        } else if name == MigrateSubcommand::Redo.as_str() {
+                            migrate::redo(get_database(arg_matches));
                         }
                     }
                     None => (),
                 }
             } else if name == Subcommand::SyncServers.as_str() {
                 match arg_matches.subcommand() {
-                    Some((name, _arg_matches)) => {
+                    Some((name, arg_matches)) => {
                         if name == SyncServersSubcommand::List.as_str() {
                             sync_servers::list().await;
+                        } else if name == SyncServersSubcommand::Serve.as_str() {
+                            rocal_dev_server::run_sync_server(
+                                arg_matches
+                                    .get_one::<String>(SyncServersCommandArg::Port.as_str())
+                                    .map(|port| port.as_str()),
+                                arg_matches
+                                    .get_one::<String>(SyncServersCommandArg::Host.as_str())
+                                    .map(|host| host.as_str()),
+                                arg_matches
+                                    .get_one::<String>(SyncServersCommandArg::PublicUrl.as_str())
+                                    .map(|public_url| public_url.as_str()),
+                                arg_matches
+                                    .get_one::<String>(SyncServersCommandArg::Directory.as_str())
+                                    .map(|directory| directory.as_str()),
+                                arg_matches
+                                    .get_many::<String>(SyncServersCommandArg::AllowOrigin.as_str())
+                                    .map(|origins| origins.cloned().collect())
+                                    .unwrap_or_default(),
+                            );
                         }
                     }
                     None => (),
@@ -136,6 +225,21 @@ pub async fn run() {
     }
 }
 
+fn database_arg() -> Arg {
+    Arg::new(MigrateCommandArg::Database)
+        .short('d')
+        .long("database")
+        .required(false)
+        .default_value("db/local.sqlite3")
+        .help("Set a path to a local copy of the database")
+}
+
+fn get_database(arg_matches: &ArgMatches) -> &str {
+    arg_matches
+        .get_one::<String>(MigrateCommandArg::Database.as_str())
+        .expect("default value")
+}
+
 enum Subcommand {
     Register,
     Login,
@@ -160,6 +264,15 @@ enum InitCommandArg {
 
 enum SyncServersSubcommand {
     List,
+    Serve,
+}
+
+enum SyncServersCommandArg {
+    Port,
+    Host,
+    PublicUrl,
+    Directory,
+    AllowOrigin,
 }
 
 enum RunCommandArg {
@@ -168,6 +281,14 @@ enum RunCommandArg {
 
 enum MigrateSubcommand {
     Add,
+    Status,
+    Rollback,
+    Redo,
+}
+
+enum MigrateCommandArg {
+    Steps,
+    Database,
 }
 
 impl Into<Str> for Subcommand {
@@ -232,6 +353,25 @@ impl SyncServersSubcommand {
     pub fn as_str(self) -> &'static str {
         match self {
             SyncServersSubcommand::List => "ls",
+            SyncServersSubcommand::Serve => "serve",
+        }
+    }
+}
+
+impl Into<Id> for SyncServersCommandArg {
+    fn into(self) -> Id {
+        self.as_str().into()
+    }
+}
+
+impl SyncServersCommandArg {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SyncServersCommandArg::Port => "port",
+            SyncServersCommandArg::Host => "host",
+            SyncServersCommandArg::PublicUrl => "public-url",
+            SyncServersCommandArg::Directory => "directory",
+            SyncServersCommandArg::AllowOrigin => "allow-origin",
         }
     }
 }
@@ -246,6 +386,24 @@ impl MigrateSubcommand {
     pub fn as_str(self) -> &'static str {
         match self {
             MigrateSubcommand::Add => "add",
+            MigrateSubcommand::Status => "status",
+            MigrateSubcommand::Rollback => "rollback",
+            MigrateSubcommand::Redo => "redo",
+        }
+    }
+}
+
+impl Into<Id> for MigrateCommandArg {
+    fn into(self) -> Id {
+        self.as_str().into()
+    }
+}
+
+impl MigrateCommandArg {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MigrateCommandArg::Steps => "steps",
+            MigrateCommandArg::Database => "database",
         }
     }
 }
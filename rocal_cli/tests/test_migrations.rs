@@ -0,0 +1,214 @@
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use rocal_cli::migrations::{
+        apply_migration, get_applied_versions, get_last_applied, get_migrations, get_status,
+        open_database, roll_back_migration, Status,
+    };
+    use rusqlite::Connection;
+
+    // Makes an app with three migrations and a database which has the first two applied.
+    fn setup() -> PathBuf {
+        let root = env::temp_dir().join(format!("rocal-migrate-{}", uuid::Uuid::new_v4()));
+        let dir = root.join("db/migrations");
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(
+            dir.join("20250101000000-create-notes-table.sql"),
+            "-- migrate:up\ncreate table notes (id integer primary key, body text);\n\n-- migrate:down\ndrop table notes;\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("20250102000000-create-tags-table.sql"),
+            "-- migrate:up\ncreate table tags (id integer primary key, name text);\n\n-- migrate:down\ndrop table tags;\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("20250103000000-add-title-to-notes.sql"),
+            "-- migrate:up\nalter table notes add column title text;\n",
+        )
+        .unwrap();
+
+        let migrations = get_migrations(&dir).unwrap();
+        let mut conn = Connection::open(root.join("local.sqlite3")).unwrap();
+
+        for migration in &migrations[..2] {
+            apply_migration(&mut conn, migration).unwrap();
+        }
+
+        root
+    }
+
+    fn tables(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("select name from sqlite_master where type = 'table' order by name;")
+            .unwrap();
+
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_status() {
+        let root = setup();
+        let migrations = get_migrations(&root.join("db/migrations")).unwrap();
+        let conn = open_database(&root.join("local.sqlite3"), true).unwrap();
+
+        assert_eq!(
+            get_status(&conn, &migrations[..2]).unwrap(),
+            vec![
+                (
+                    Status::Up,
+                    "20250101000000-create-notes-table.sql".to_string()
+                ),
+                (
+                    Status::Up,
+                    "20250102000000-create-tags-table.sql".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            get_status(&conn, &migrations[1..]).unwrap(),
+            vec![
+                (
+                    Status::Up,
+                    "20250102000000-create-tags-table.sql".to_string()
+                ),
+                (
+                    Status::Down,
+                    "20250103000000-add-title-to-notes.sql".to_string()
+                ),
+                (Status::FileNotFound, "20250101000000".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status_leaves_a_database_as_it_is() {
+        let root = setup();
+        let migrations = get_migrations(&root.join("db/migrations")).unwrap();
+        let path = root.join("empty.sqlite3");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("create table notes (id integer primary key);")
+            .unwrap();
+
+        let conn = open_database(&path, true).unwrap();
+
+        assert!(get_status(&conn, &migrations)
+            .unwrap()
+            .iter()
+            .all(|(status, _)| *status == Status::Down));
+        assert_eq!(tables(&conn), vec!["notes"]);
+        assert!(conn
+            .execute_batch("create table tags (id integer primary key);")
+            .is_err());
+    }
+
+    #[test]
+    fn test_rollback() {
+        let root = setup();
+        let migrations = get_migrations(&root.join("db/migrations")).unwrap();
+        let mut conn = open_database(&root.join("local.sqlite3"), false).unwrap();
+
+        let last_applied = get_last_applied(&conn, &migrations, 2).unwrap();
+        assert_eq!(
+            last_applied
+                .iter()
+                .map(|migration| migration.get_version())
+                .collect::<Vec<_>>(),
+            vec!["20250102000000", "20250101000000"]
+        );
+
+        for migration in last_applied {
+            roll_back_migration(&mut conn, migration).unwrap();
+        }
+
+        assert!(get_applied_versions(&conn).unwrap().is_empty());
+        assert_eq!(tables(&conn), vec!["schema_migrations"]);
+        assert!(get_last_applied(&conn, &migrations, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rollback_without_down_section() {
+        let root = setup();
+        let migrations = get_migrations(&root.join("db/migrations")).unwrap();
+        let mut conn = open_database(&root.join("local.sqlite3"), false).unwrap();
+
+        apply_migration(&mut conn, &migrations[2]).unwrap();
+
+        assert_eq!(
+            roll_back_migration(&mut conn, &migrations[2]),
+            Err("20250103000000-add-title-to-notes.sql has no -- migrate:down section".to_string())
+        );
+        assert_eq!(get_applied_versions(&conn).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_rollback_with_empty_down_section() {
+        let root = setup();
+        let dir = root.join("db/migrations");
+        fs::write(
+            dir.join("20250104000000-add-body-to-tags.sql"),
+            "-- migrate:up\nalter table tags add column body text;\n\n-- migrate:down\n\n",
+        )
+        .unwrap();
+
+        let migrations = get_migrations(&dir).unwrap();
+        let mut conn = open_database(&root.join("local.sqlite3"), false).unwrap();
+
+        apply_migration(&mut conn, &migrations[3]).unwrap();
+
+        assert_eq!(
+            roll_back_migration(&mut conn, &migrations[3]),
+            Err(
+                "20250104000000-add-body-to-tags.sql has an empty -- migrate:down section"
+                    .to_string()
+            )
+        );
+        assert_eq!(get_applied_versions(&conn).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_migrations_without_version() {
+        let root = setup();
+        let dir = root.join("db/migrations");
+        fs::write(dir.join("create-users-table.sql"), "-- migrate:up\n").unwrap();
+
+        assert_eq!(
+            get_migrations(&dir).err(),
+            Some(
+                "create-users-table.sql should start with a timestamp e.g. <timestamp>-<name>.sql"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_redo() {
+        let root = setup();
+        let migrations = get_migrations(&root.join("db/migrations")).unwrap();
+        let mut conn = open_database(&root.join("local.sqlite3"), false).unwrap();
+
+        conn.execute_batch("insert into tags (name) values ('rust');")
+            .unwrap();
+
+        let last_applied = get_last_applied(&conn, &migrations, 1).unwrap();
+        let migration = last_applied[0];
+
+        roll_back_migration(&mut conn, migration).unwrap();
+        apply_migration(&mut conn, migration).unwrap();
+
+        let tags: i64 = conn
+            .query_row("select count(*) from tags;", [], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(tags, 0);
+        assert_eq!(
+            get_applied_versions(&conn).unwrap(),
+            vec!["20250101000000", "20250102000000"]
+        );
+    }
+}
@@ -1,25 +1,49 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{
+    braced,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
     Ident, LitStr, Token,
 };
 
+use crate::enums::{
+    encryption::Encryption, routing_mode::RoutingMode, storage::Storage, sync_mode::SyncMode,
+};
+
 pub fn build_config_struct() -> TokenStream {
     quote! {
         pub struct Configuration {
             app_id: String,
             sync_server_endpoint: String,
+            sync_mode: rocal::rocal_core::enums::sync_mode::SyncMode,
+            encryption: rocal::rocal_core::enums::encryption::Encryption,
+            storage_fallback: rocal::rocal_core::enums::storage::Storage,
+            routing_mode: rocal::rocal_core::enums::routing_mode::RoutingMode,
             database: std::sync::Arc<crate::Database>,
+            databases: Vec<NamedDatabase>,
         }
 
         impl Configuration {
-            pub fn new(app_id: String, sync_server_endpoint: String, database: std::sync::Arc<crate::Database>) -> Self {
+            pub fn new(
+                app_id: String,
+                sync_server_endpoint: String,
+                sync_mode: rocal::rocal_core::enums::sync_mode::SyncMode,
+                encryption: rocal::rocal_core::enums::encryption::Encryption,
+                storage_fallback: rocal::rocal_core::enums::storage::Storage,
+                routing_mode: rocal::rocal_core::enums::routing_mode::RoutingMode,
+                database: std::sync::Arc<crate::Database>,
+                databases: Vec<NamedDatabase>,
+            ) -> Self {
                 Configuration {
                     app_id,
                     sync_server_endpoint,
+                    sync_mode,
+                    encryption,
+                    storage_fallback,
+                    routing_mode,
                     database,
+                    databases,
                 }
             }
 
@@ -31,15 +55,80 @@ pub fn build_config_struct() -> TokenStream {
                 &self.sync_server_endpoint
             }
 
+            pub fn get_sync_mode(&self) -> &rocal::rocal_core::enums::sync_mode::SyncMode {
+                &self.sync_mode
+            }
+
+            pub fn get_encryption(&self) -> &rocal::rocal_core::enums::encryption::Encryption {
+                &self.encryption
+            }
+
+            /// Where databases are kept when the browser doesn't support OPFS.
+            pub fn get_storage_fallback(&self) -> &rocal::rocal_core::enums::storage::Storage {
+                &self.storage_fallback
+            }
+
+            pub fn get_routing_mode(&self) -> &rocal::rocal_core::enums::routing_mode::RoutingMode {
+                &self.routing_mode
+            }
+
+            pub fn get_database(&self) -> std::sync::Arc<crate::Database> {
+                self.database.clone()
+            }
+
+            /// Returns the database declared under `databases` in `config!`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if no database has the name.
+            pub fn database(&self, name: &str) -> std::sync::Arc<crate::Database> {
+                match self.databases.iter().find(|database| database.get_name() == name) {
+                    Some(database) => database.get_database(),
+                    None => panic!("No database named {} is declared in config!", name),
+                }
+            }
+
+            pub fn get_databases(&self) -> &Vec<NamedDatabase> {
+                &self.databases
+            }
+        }
+
+        pub struct NamedDatabase {
+            name: String,
+            database: std::sync::Arc<crate::Database>,
+            sync_mode: rocal::rocal_core::enums::sync_mode::SyncMode,
+        }
+
+        impl NamedDatabase {
+            pub fn new(
+                name: String,
+                database: std::sync::Arc<crate::Database>,
+                sync_mode: rocal::rocal_core::enums::sync_mode::SyncMode,
+            ) -> Self {
+                NamedDatabase {
+                    name,
+                    database,
+                    sync_mode,
+                }
+            }
+
+            pub fn get_name(&self) -> &str {
+                &self.name
+            }
+
             pub fn get_database(&self) -> std::sync::Arc<crate::Database> {
                 self.database.clone()
             }
+
+            pub fn get_sync_mode(&self) -> &rocal::rocal_core::enums::sync_mode::SyncMode {
+                &self.sync_mode
+            }
         }
     }
 }
 
 pub fn parse_config(item: TokenStream) -> Result<ParsedConfig, syn::Error> {
-    let parsed_config: ParsedConfig = syn::parse(item.into())?;
+    let parsed_config: ParsedConfig = syn::parse2(item)?;
 
     Ok(parsed_config)
 }
@@ -50,6 +139,11 @@ pub struct ParsedConfig {
     sync_server_endpoint: Option<String>,
     database_directory_name: Option<String>,
     database_file_name: Option<String>,
+    sync_mode: Option<SyncMode>,
+    encryption: Option<Encryption>,
+    storage_fallback: Option<Storage>,
+    routing_mode: Option<RoutingMode>,
+    databases: Vec<ParsedDatabase>,
 }
 
 impl ParsedConfig {
@@ -69,6 +163,26 @@ impl ParsedConfig {
         self.database_file_name = Some(file_name);
     }
 
+    pub fn set_sync_mode(&mut self, sync_mode: SyncMode) {
+        self.sync_mode = Some(sync_mode);
+    }
+
+    pub fn set_encryption(&mut self, encryption: Encryption) {
+        self.encryption = Some(encryption);
+    }
+
+    pub fn set_storage_fallback(&mut self, storage_fallback: Storage) {
+        self.storage_fallback = Some(storage_fallback);
+    }
+
+    pub fn set_routing_mode(&mut self, routing_mode: RoutingMode) {
+        self.routing_mode = Some(routing_mode);
+    }
+
+    pub fn add_database(&mut self, database: ParsedDatabase) {
+        self.databases.push(database);
+    }
+
     pub fn get_app_id(&self) -> &Option<String> {
         &self.app_id
     }
@@ -84,22 +198,138 @@ impl ParsedConfig {
     pub fn get_database_file_name(&self) -> &Option<String> {
         &self.database_file_name
     }
+
+    pub fn get_sync_mode(&self) -> &Option<SyncMode> {
+        &self.sync_mode
+    }
+
+    pub fn get_encryption(&self) -> &Option<Encryption> {
+        &self.encryption
+    }
+
+    pub fn get_storage_fallback(&self) -> &Option<Storage> {
+        &self.storage_fallback
+    }
+
+    pub fn get_routing_mode(&self) -> &Option<RoutingMode> {
+        &self.routing_mode
+    }
+
+    pub fn get_databases(&self) -> &Vec<ParsedDatabase> {
+        &self.databases
+    }
+}
+
+/// A database declared under `databases`, e.g. `cache: { file_name: "cache.sqlite3" }`.
+#[derive(Debug)]
+pub struct ParsedDatabase {
+    name: String,
+    directory_name: Option<String>,
+    file_name: Option<String>,
+    sync_mode: Option<SyncMode>,
+}
+
+impl ParsedDatabase {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_directory_name(&self) -> &Option<String> {
+        &self.directory_name
+    }
+
+    pub fn get_file_name(&self) -> &Option<String> {
+        &self.file_name
+    }
+
+    pub fn get_sync_mode(&self) -> &Option<SyncMode> {
+        &self.sync_mode
+    }
 }
 
 impl Parse for ParsedConfig {
     fn parse(input: ParseStream) -> Result<Self, syn::Error> {
         let mut config = ParsedConfig::default();
-
-        let kvs = Punctuated::<KeyValue, Token!(,)>::parse_terminated(&input)?;
         let mut has_error_attribute = false;
 
-        kvs.into_iter().for_each(|kv| match kv.key.as_str() {
-            "app_id" => config.set_app_id(kv.value),
-            "sync_server_endpoint" => config.set_sync_server_endpoint(kv.value),
-            "database_directory_name" => config.set_database_directory_name(kv.value),
-            "database_file_name" => config.set_database_file_name(kv.value),
-            _ => has_error_attribute = true,
-        });
+        while !input.is_empty() {
+            if input.peek(Ident) && input.peek2(Token!(:)) && input.peek3(syn::token::Brace) {
+                let key: Ident = input.parse()?;
+                let _: Token!(:) = input.parse()?;
+
+                if key != "databases" {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "Only databases can have a block as its value",
+                    ));
+                }
+
+                let content;
+                braced!(content in input);
+
+                for database in Punctuated::<ParsedDatabase, Token!(,)>::parse_terminated(&content)?
+                {
+                    if config
+                        .get_databases()
+                        .iter()
+                        .any(|declared| declared.get_name() == database.get_name())
+                    {
+                        return Err(syn::Error::new(
+                            content.span(),
+                            format!("{} is declared more than once", database.get_name()),
+                        ));
+                    }
+
+                    config.add_database(database);
+                }
+            } else {
+                let kv: KeyValue = input.parse()?;
+
+                match kv.key.as_str() {
+                    "app_id" => config.set_app_id(kv.value),
+                    "sync_server_endpoint" => config.set_sync_server_endpoint(kv.value),
+                    "database_directory_name" => config.set_database_directory_name(kv.value),
+                    "database_file_name" => config.set_database_file_name(kv.value),
+                    "sync_mode" => config.set_sync_mode(parse_sync_mode(&kv, input)?),
+                    "encryption" => match Encryption::from(&kv.value) {
+                        Some(encryption) => config.set_encryption(encryption),
+                        None => {
+                            return Err(syn::Error::new(
+                                input.span(),
+                                "encryption should be \"none\" or \"passphrase\"",
+                            ))
+                        }
+                    },
+                    "routing_mode" => match RoutingMode::from(&kv.value) {
+                        Some(routing_mode) => config.set_routing_mode(routing_mode),
+                        None => {
+                            return Err(syn::Error::new(
+                                input.span(),
+                                "routing_mode should be \"hash\" or \"history\"",
+                            ))
+                        }
+                    },
+                    "storage_fallback" => match Storage::from(&kv.value) {
+                        Some(storage) if storage != Storage::Opfs => {
+                            config.set_storage_fallback(storage)
+                        }
+                        _ => {
+                            return Err(syn::Error::new(
+                                input.span(),
+                                "storage_fallback should be \"memory\", \"local_storage\", \"session_storage\" or \"none\"",
+                            ))
+                        }
+                    },
+                    _ => has_error_attribute = true,
+                }
+            }
+
+            if input.is_empty() {
+                break;
+            }
+
+            let _: Token!(,) = input.parse()?;
+        }
 
         if has_error_attribute {
             return Err(syn::Error::new(
@@ -108,10 +338,73 @@ impl Parse for ParsedConfig {
             ));
         }
 
+        let uses_changeset = config.get_sync_mode() == &Some(SyncMode::Changeset)
+            || config
+                .get_databases()
+                .iter()
+                .any(|database| database.get_sync_mode() == &Some(SyncMode::Changeset));
+
+        if config.get_encryption() == &Some(Encryption::Passphrase) && uses_changeset {
+            return Err(syn::Error::new(
+                input.span(),
+                "encryption is only available with sync_mode: \"snapshot\"",
+            ));
+        }
+
         Ok(config)
     }
 }
 
+impl Parse for ParsedDatabase {
+    fn parse(input: ParseStream) -> Result<Self, syn::Error> {
+        let name: Ident = input
+            .parse()
+            .map_err(|_| syn::Error::new(input.span(), "should have a database name"))?;
+
+        let _: Token!(:) = input.parse().map_err(|_| {
+            syn::Error::new(
+                input.span(),
+                "database name and value should be separated by :",
+            )
+        })?;
+
+        let content;
+        braced!(content in input);
+
+        let mut database = ParsedDatabase {
+            name: name.to_string(),
+            directory_name: None,
+            file_name: None,
+            sync_mode: None,
+        };
+
+        for kv in Punctuated::<KeyValue, Token!(,)>::parse_terminated(&content)? {
+            match kv.key.as_str() {
+                "directory_name" => database.directory_name = Some(kv.value),
+                "file_name" => database.file_name = Some(kv.value),
+                "sync_mode" => database.sync_mode = Some(parse_sync_mode(&kv, &content)?),
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!("{} is not a property of a database", kv.key),
+                    ))
+                }
+            }
+        }
+
+        Ok(database)
+    }
+}
+
+fn parse_sync_mode(kv: &KeyValue, input: ParseStream) -> Result<SyncMode, syn::Error> {
+    SyncMode::from(&kv.value).ok_or_else(|| {
+        syn::Error::new(
+            input.span(),
+            "sync_mode should be \"snapshot\", \"changeset\" or \"none\"",
+        )
+    })
+}
+
 struct KeyValue {
     key: String,
     value: String,
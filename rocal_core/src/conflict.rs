@@ -0,0 +1,76 @@
+use serde::{de::DeserializeOwned, Deserialize};
+use wasm_bindgen::JsValue;
+
+use crate::enums::{
+    conflict::{ConflictKind, ConflictOperation},
+    database_error::DatabaseError,
+};
+
+/// A change pulled from another device which conflicts with a row on this device.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Conflict {
+    table: String,
+    kind: ConflictKind,
+    operation: ConflictOperation,
+    columns: Vec<String>,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    old: JsValue,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    remote: JsValue,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    local: JsValue,
+    pending_local: bool,
+}
+
+impl Conflict {
+    pub fn get_table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn get_kind(&self) -> &ConflictKind {
+        &self.kind
+    }
+
+    pub fn get_operation(&self) -> &ConflictOperation {
+        &self.operation
+    }
+
+    pub fn get_columns(&self) -> &Vec<String> {
+        &self.columns
+    }
+
+    /// Whether the row also has a change on this device which hasn't been pushed yet.
+    pub fn get_pending_local(&self) -> &bool {
+        &self.pending_local
+    }
+
+    /// The row before the remote change. Columns which an update didn't touch are null.
+    pub fn get_old<T: DeserializeOwned>(&self) -> Result<Option<T>, DatabaseError> {
+        self.to_row(&self.old)
+    }
+
+    /// The row after the remote change. Columns which an update didn't touch are null.
+    pub fn get_remote<T: DeserializeOwned>(&self) -> Result<Option<T>, DatabaseError> {
+        self.to_row(&self.remote)
+    }
+
+    /// The row on this device, if it exists.
+    pub fn get_local<T: DeserializeOwned>(&self) -> Result<Option<T>, DatabaseError> {
+        self.to_row(&self.local)
+    }
+
+    fn to_row<T: DeserializeOwned>(&self, values: &JsValue) -> Result<Option<T>, DatabaseError> {
+        if values.is_null() || values.is_undefined() {
+            return Ok(None);
+        }
+
+        let values = js_sys::Array::from(values);
+        let row = js_sys::Object::new();
+
+        for (i, column) in self.columns.iter().enumerate() {
+            js_sys::Reflect::set(&row, &JsValue::from_str(column), &values.get(i as u32))?;
+        }
+
+        Ok(Some(serde_wasm_bindgen::from_value(row.into())?))
+    }
+}
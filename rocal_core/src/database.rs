@@ -7,6 +7,7 @@ pub fn build_database_struct() -> TokenStream {
         use wasm_bindgen::{JsCast, JsValue};
         use serde::de::DeserializeOwned;
         use serde_wasm_bindgen::from_value;
+        use rocal::rocal_core::enums::database_error::DatabaseError;
 
         pub struct Database {
             directory_name: String,
@@ -33,51 +34,639 @@ pub fn build_database_struct() -> TokenStream {
                 format!("{}/{}", self.directory_name, self.file_name)
             }
 
+            /// A query made inside the closure of `transaction` fails with
+            /// `DatabaseError::QueryOutsideTransaction`, since it would wait for the transaction
+            /// to end forever. Use the query of the transaction there.
             pub fn query(&self, query: &str) -> Query {
-                Query::new(&self.get_name(), query)
+                let mut query = Query::new(&self.get_name(), query, None);
+
+                if TRANSACTION_SCOPES.with(|scopes| scopes.borrow().contains(&self.get_name())) {
+                    query.error = Some(DatabaseError::QueryOutsideTransaction);
+                }
+
+                query
+            }
+
+            /// Other queries on this database wait until the returned transaction is committed or
+            /// rolled back, so don't await a query made by `Database::query` while holding it. The
+            /// transaction is rolled back when it's dropped without being finished, e.g. by an
+            /// early return with `?`, as long as no clone of it is kept.
+            pub async fn begin(&self) -> Result<Transaction, DatabaseError> {
+                self.begin_transaction(false).await
+            }
+
+            // The writes of an `untracked` transaction aren't recorded as changes to push.
+            async fn begin_transaction(&self, untracked: bool) -> Result<Transaction, DatabaseError> {
+                let id = crate::new_transaction_id();
+
+                // Made before beginning so that the transaction is rolled back even if this
+                // future is dropped while waiting.
+                let guard = std::rc::Rc::new(TransactionGuard {
+                    db: self.get_name(),
+                    id,
+                    finished: std::cell::Cell::new(false),
+                });
+
+                let promise = crate::begin_transaction(&self.get_name(), id, untracked).dyn_into::<Promise>()?;
+
+                if let Err(err) = wasm_bindgen_futures::JsFuture::from(promise).await {
+                    guard.finished.set(true);
+                    return Err(err.into());
+                }
+
+                Ok(Transaction {
+                    db: self.get_name(),
+                    id,
+                    guard,
+                })
+            }
+
+            /// Runs `f` in a transaction, which is committed if `f` returns `Ok` and rolled back
+            /// otherwise, including when the returned future is dropped before it's done.
+            pub async fn transaction<F, Fut, T>(&self, f: F) -> Result<T, DatabaseError>
+            where
+                F: FnOnce(Transaction) -> Fut,
+                Fut: std::future::Future<Output = Result<T, DatabaseError>>,
+            {
+                let tx = self.begin().await?;
+                let scoped = TransactionScope {
+                    db: self.get_name(),
+                    future: Box::pin(f(tx.clone())),
+                };
+
+                match scoped.await {
+                    Ok(result) => {
+                        tx.commit().await?;
+                        Ok(result)
+                    }
+                    Err(err) => {
+                        let _ = tx.rollback().await;
+                        Err(err)
+                    }
+                }
+            }
+
+            /// Runs the query now and again whenever a write on this database touches a table it
+            /// reads, passing the rows to `callback` each time. It keeps watching until the returned
+            /// subscription is dropped.
+            #[must_use]
+            pub fn watch<T, F>(&self, query: &Query, callback: F) -> Subscription
+            where
+                T: DeserializeOwned + 'static,
+                F: Fn(Result<Vec<T>, DatabaseError>) + 'static,
+            {
+                CHANGE_LISTENER.with(|listener| {
+                    listener.get_or_init(|| {
+                        let listener = Closure::new(notify_watchers);
+                        crate::on_database_change(&listener);
+                        listener
+                    });
+                });
+
+                let id = NEXT_WATCHER_ID.with(|next| {
+                    let id = next.get();
+                    next.set(id + 1);
+                    id
+                });
+
+                let query = Query {
+                    db: self.get_name(),
+                    transaction: None,
+                    ..query.clone()
+                };
+                let callback = std::rc::Rc::new(callback);
+
+                // Only the result of the latest refresh is passed, so that an older one which
+                // finishes later doesn't overwrite it.
+                let refresh: std::rc::Rc<dyn Fn()> = {
+                    let query = query.clone();
+                    let callback = callback.clone();
+                    let latest = std::rc::Rc::new(std::cell::Cell::new(0u64));
+
+                    std::rc::Rc::new(move || {
+                        let query = query.clone();
+                        let callback = callback.clone();
+                        let latest = latest.clone();
+                        let sequence = latest.get() + 1;
+                        latest.set(sequence);
+
+                        wasm_bindgen_futures::spawn_local(async move {
+                            let result = query.fetch().await;
+
+                            if latest.get() == sequence {
+                                callback(result);
+                            }
+                        });
+                    })
+                };
+
+                WATCHERS.with(|watchers| {
+                    watchers.borrow_mut().push(Watcher {
+                        id,
+                        db: self.get_name(),
+                        tables: vec![],
+                        refresh: refresh.clone(),
+                    });
+                });
+
+                wasm_bindgen_futures::spawn_local(async move {
+                    match query.get_tables().await {
+                        Ok(tables) => {
+                            WATCHERS.with(|watchers| {
+                                if let Some(watcher) = watchers
+                                    .borrow_mut()
+                                    .iter_mut()
+                                    .find(|watcher| watcher.id == id)
+                                {
+                                    watcher.tables = tables;
+                                }
+                            });
+
+                            refresh();
+                        }
+                        Err(err) => callback(Err(err)),
+                    }
+                });
+
+                Subscription { id }
+            }
+
+            /// Where the database is kept, so that the app can warn that data won't persist when
+            /// the browser doesn't support OPFS.
+            pub async fn storage(&self) -> Result<rocal::rocal_core::enums::storage::Storage, DatabaseError> {
+                let promise = crate::database_storage(&self.get_name()).dyn_into::<Promise>()?;
+                let storage = wasm_bindgen_futures::JsFuture::from(promise).await?;
+
+                storage
+                    .as_string()
+                    .and_then(|storage| rocal::rocal_core::enums::storage::Storage::from(&storage))
+                    .ok_or_else(|| DatabaseError::Unknown("Failed to get the storage of the database".to_string()))
+            }
+
+            pub fn sync_handle(&self) -> SyncHandle {
+                SyncHandle::new(self.get_name())
+            }
+
+            /// Starts recording changes to the database so that they can be pushed as changesets.
+            pub async fn track_changes(&self) -> Result<(), DatabaseError> {
+                let promise = crate::track_changes(&self.get_name()).dyn_into::<Promise>()?;
+                wasm_bindgen_futures::JsFuture::from(promise).await?;
+                Ok(())
+            }
+
+            /// Decides what to do when a change pulled from another device conflicts with a row on
+            /// this device. Without it, a row which also has a change waiting to be pushed keeps the
+            /// local version, and any other row takes the remote one.
+            pub fn set_conflict_resolver<F>(&self, resolver: F)
+            where
+                F: Fn(&rocal::rocal_core::conflict::Conflict) -> rocal::rocal_core::enums::conflict::ConflictResolution + 'static,
+            {
+                CONFLICT_RESOLVERS.with(|resolvers| {
+                    resolvers
+                        .borrow_mut()
+                        .insert(self.get_name(), std::rc::Rc::new(resolver));
+                });
+            }
+
+            /// Returns the database file, e.g. for users to download a backup.
+            pub async fn export(&self) -> Result<Vec<u8>, DatabaseError> {
+                let promise = crate::export_database(&self.get_name()).dyn_into::<Promise>()?;
+                let bytes = wasm_bindgen_futures::JsFuture::from(promise).await?;
+
+                Ok(js_sys::Uint8Array::new(&bytes).to_vec())
+            }
+
+            /// Replaces the database with a file made by `export`, after applying the migrations
+            /// given to `migrate` which the file doesn't have yet. The file is checked and
+            /// migrated before it replaces the database, so a broken file, or one which still
+            /// lacks a migration of the database because `migrate` hasn't been called yet since
+            /// the page was loaded, leaves the database as it was. Queries wait while the file is
+            /// replaced, and live queries are refreshed afterwards.
+            pub async fn import(&self, bytes: &[u8]) -> Result<(), DatabaseError> {
+                let migrations = MIGRATIONS.with(|migrations| {
+                    migrations
+                        .borrow()
+                        .get(&self.get_name())
+                        .cloned()
+                        .unwrap_or_default()
+                });
+                let migrations = serde_wasm_bindgen::to_value(&migrations)?;
+
+                let promise = crate::import_database(&self.get_name(), bytes, migrations)
+                    .dyn_into::<Promise>()?;
+                wasm_bindgen_futures::JsFuture::from(promise).await?;
+
+                Ok(())
+            }
+
+            pub async fn migrate(&self, migrations: &[(&str, &str)]) -> Result<(), DatabaseError> {
+                #[derive(serde::Deserialize)]
+                struct SchemaMigration {
+                    version: String,
+                }
+
+                // Kept to be applied again to a file given to `import`.
+                MIGRATIONS.with(|recorded| {
+                    let mut recorded = recorded.borrow_mut();
+                    let recorded = recorded.entry(self.get_name()).or_insert_with(Vec::new);
+
+                    for (version, query) in migrations {
+                        if !recorded.iter().any(|(recorded, _)| recorded == version) {
+                            recorded.push((version.to_string(), query.to_string()));
+                        }
+                    }
+                });
+
+                self.query(
+                    "create table if not exists schema_migrations (version text primary key, applied_at datetime default current_timestamp);",
+                )
+                .execute()
+                .await?;
+
+                let applied: Vec<SchemaMigration> = self
+                    .query("select version from schema_migrations;")
+                    .fetch()
+                    .await?;
+
+                for (version, query) in migrations {
+                    if applied.iter().any(|migration| migration.version == *version) {
+                        continue;
+                    }
+
+                    // Every device runs the migrations by itself, so what they write, e.g. seed
+                    // rows, isn't pushed as changes.
+                    let tx = self.begin_transaction(true).await?;
+
+                    let result: Result<(), DatabaseError> = async {
+                        tx.query(query).execute().await?;
+                        tx.query("insert into schema_migrations (version) values ($1);")
+                            .bind(*version)
+                            .execute()
+                            .await?;
+                        Ok(())
+                    }
+                    .await;
+
+                    match result {
+                        Ok(()) => tx.commit().await?,
+                        Err(err) => {
+                            let _ = tx.rollback().await;
+                            return Err(err);
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+        }
+
+        #[derive(Clone)]
+        pub struct Transaction {
+            db: String,
+            id: u32,
+            guard: std::rc::Rc<TransactionGuard>,
+        }
+
+        impl Transaction {
+            pub fn query(&self, query: &str) -> Query {
+                Query::new(&self.db, query, Some(self.id))
+            }
+
+            pub async fn commit(&self) -> Result<(), DatabaseError> {
+                self.guard.finished.set(true);
+
+                let promise = crate::commit_transaction(&self.db, self.id).dyn_into::<Promise>()?;
+                wasm_bindgen_futures::JsFuture::from(promise).await?;
+                Ok(())
+            }
+
+            pub async fn rollback(&self) -> Result<(), DatabaseError> {
+                self.guard.finished.set(true);
+
+                let promise = crate::rollback_transaction(&self.db, self.id).dyn_into::<Promise>()?;
+                wasm_bindgen_futures::JsFuture::from(promise).await?;
+                Ok(())
+            }
+        }
+
+        // Shared by the clones of a transaction, and rolls it back when the last one is dropped
+        // before it's committed or rolled back.
+        struct TransactionGuard {
+            db: String,
+            id: u32,
+            finished: std::cell::Cell<bool>,
+        }
+
+        impl Drop for TransactionGuard {
+            fn drop(&mut self) {
+                if !self.finished.get() {
+                    let _ = crate::rollback_transaction(&self.db, self.id);
+                }
+            }
+        }
+
+        // Marks the database while the closure of `Database::transaction` runs, so that
+        // `Database::query` can tell that it's made inside it.
+        struct TransactionScope<F> {
+            db: String,
+            future: std::pin::Pin<Box<F>>,
+        }
+
+        impl<F: std::future::Future> std::future::Future for TransactionScope<F> {
+            type Output = F::Output;
+
+            fn poll(
+                mut self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<Self::Output> {
+                TRANSACTION_SCOPES.with(|scopes| scopes.borrow_mut().push(self.db.clone()));
+                let poll = self.future.as_mut().poll(cx);
+                TRANSACTION_SCOPES.with(|scopes| scopes.borrow_mut().pop());
+
+                poll
+            }
+        }
+
+        struct Watcher {
+            id: u32,
+            db: String,
+            tables: Vec<String>,
+            refresh: std::rc::Rc<dyn Fn()>,
+        }
+
+        type ConflictResolver = std::rc::Rc<
+            dyn Fn(&rocal::rocal_core::conflict::Conflict) -> rocal::rocal_core::enums::conflict::ConflictResolution,
+        >;
+
+        thread_local! {
+            static WATCHERS: std::cell::RefCell<Vec<Watcher>> = std::cell::RefCell::new(vec![]);
+            static NEXT_WATCHER_ID: std::cell::Cell<u32> = std::cell::Cell::new(0);
+            static CHANGE_LISTENER: std::cell::OnceCell<Closure<dyn Fn(String, js_sys::Array)>> =
+                std::cell::OnceCell::new();
+            static CONFLICT_RESOLVERS: std::cell::RefCell<std::collections::HashMap<String, ConflictResolver>> =
+                std::cell::RefCell::new(std::collections::HashMap::new());
+            static TRANSACTION_SCOPES: std::cell::RefCell<Vec<String>> = std::cell::RefCell::new(vec![]);
+            static MIGRATIONS: std::cell::RefCell<std::collections::HashMap<String, Vec<(String, String)>>> =
+                std::cell::RefCell::new(std::collections::HashMap::new());
+        }
+
+        // Called by the query worker with the tables which a write has touched.
+        fn notify_watchers(db: String, tables: js_sys::Array) {
+            let tables: Vec<String> = tables.iter().filter_map(|table| table.as_string()).collect();
+
+            let refreshes: Vec<std::rc::Rc<dyn Fn()>> = WATCHERS.with(|watchers| {
+                watchers
+                    .borrow()
+                    .iter()
+                    .filter(|watcher| {
+                        watcher.db == db
+                            && watcher.tables.iter().any(|table| {
+                                tables.iter().any(|changed| changed.eq_ignore_ascii_case(table))
+                            })
+                    })
+                    .map(|watcher| watcher.refresh.clone())
+                    .collect()
+            });
+
+            for refresh in refreshes {
+                refresh();
             }
         }
 
+        /// Stops the live query made by `Database::watch` when dropped.
+        pub struct Subscription {
+            id: u32,
+        }
+
+        impl Subscription {
+            pub fn unsubscribe(self) {}
+        }
+
+        impl Drop for Subscription {
+            fn drop(&mut self) {
+                WATCHERS.with(|watchers| {
+                    watchers.borrow_mut().retain(|watcher| watcher.id != self.id);
+                });
+            }
+        }
+
+        #[derive(Clone)]
         struct Query {
             db: String,
             query: String,
             bindings: Vec<JsValue>,
+            named_bindings: Vec<(String, JsValue)>,
+            transaction: Option<u32>,
+            error: Option<DatabaseError>,
         }
 
         impl Query {
-            fn new(db: &str, query: &str) -> Self {
+            fn new(db: &str, query: &str, transaction: Option<u32>) -> Self {
                 Self {
                     db: db.to_string(),
                     query: query.to_string(),
                     bindings: vec![],
+                    named_bindings: vec![],
+                    transaction,
+                    error: None,
                 }
             }
 
             fn bind<T>(&mut self, bind: T) -> &mut Self
             where
-                T: Into<JsValue>,
+                T: rocal::rocal_core::traits::ToSql,
+            {
+                self.bindings.push(bind.to_sql());
+                self
+            }
+
+            /// Binds a value to a named parameter such as `:name`. The `:` prefix can be omitted.
+            fn bind_named<T>(&mut self, name: &str, bind: T) -> &mut Self
+            where
+                T: rocal::rocal_core::traits::ToSql,
             {
-                self.bindings.push(bind.into());
+                let name = if name.starts_with([':', '@', '$']) {
+                    name.to_string()
+                } else {
+                    format!(":{}", name)
+                };
+
+                self.named_bindings.push((name, bind.to_sql()));
                 self
             }
 
-            pub async fn fetch<T>(&self) -> Result<Vec<T>, JsValue>
+            /// Binds each field of a struct to the named parameter of the same name, e.g. `:product_id`.
+            /// Fields which the query has no parameter for are left out.
+            fn bind_struct<T>(&mut self, value: &T) -> &mut Self
+            where
+                T: serde::Serialize,
+            {
+                let serializer = serde_wasm_bindgen::Serializer::new()
+                    .serialize_maps_as_objects(true)
+                    .serialize_missing_as_null(true)
+                    .serialize_large_number_types_as_bigints(true);
+
+                let object = match value.serialize(&serializer) {
+                    Ok(object) if object.is_object() => object,
+                    Ok(_) => {
+                        self.error = Some(DatabaseError::Unknown(
+                            "bind_struct expects a struct or a map".to_string(),
+                        ));
+                        return self;
+                    }
+                    Err(err) => {
+                        self.error = Some(err.into());
+                        return self;
+                    }
+                };
+
+                // sqlite3 refuses to bind a name which the statement doesn't have.
+                let params = rocal::rocal_core::sql::get_param_names(&self.query);
+
+                for entry in js_sys::Object::entries(object.unchecked_ref()).iter() {
+                    let entry = js_sys::Array::from(&entry);
+
+                    if let Some(name) = entry.get(0).as_string() {
+                        let name = format!(":{}", name);
+
+                        if params.contains(&name) {
+                            self.named_bindings.push((name, entry.get(1)));
+                        }
+                    }
+                }
+
+                self
+            }
+
+            pub async fn fetch<T>(&self) -> Result<Vec<T>, DatabaseError>
             where
                 T: DeserializeOwned,
             {
-                let promise = crate::exec_sql(&self.db, &self.query, self.bindings.clone().into_boxed_slice())
-                    .dyn_into::<Promise>()?;
-                let result = wasm_bindgen_futures::JsFuture::from(promise).await?;
+                let result = self.run(None, None).await?;
                 let result: Vec<T> = from_value(result)?;
                 Ok(result)
             }
 
-            pub async fn execute(&self) -> Result<JsValue, JsValue> {
-                let promise = crate::exec_sql(&self.db, &self.query, self.bindings.clone().into_boxed_slice())
-                    .dyn_into::<Promise>()?;
+            pub async fn fetch_one<T>(&self) -> Result<T, DatabaseError>
+            where
+                T: DeserializeOwned,
+            {
+                self.fetch_optional()
+                    .await?
+                    .ok_or(DatabaseError::RowNotFound)
+            }
+
+            pub async fn fetch_optional<T>(&self) -> Result<Option<T>, DatabaseError>
+            where
+                T: DeserializeOwned,
+            {
+                let result = self.run(Some(2), None).await?;
+                let mut result: Vec<T> = from_value(result)?;
+
+                if 1 < result.len() {
+                    return Err(DatabaseError::TooManyRows);
+                }
+
+                Ok(result.pop())
+            }
+
+            pub async fn fetch_scalar<T>(&self) -> Result<T, DatabaseError>
+            where
+                T: DeserializeOwned,
+            {
+                let result = self.run(Some(2), Some(0)).await?;
+                let mut result: Vec<T> = from_value(result)?;
+
+                if 1 < result.len() {
+                    return Err(DatabaseError::TooManyRows);
+                }
+
+                result.pop().ok_or(DatabaseError::RowNotFound)
+            }
+
+            pub async fn execute(&self) -> Result<JsValue, DatabaseError> {
+                self.run(None, None).await
+            }
+
+            async fn get_tables(&self) -> Result<Vec<String>, DatabaseError> {
+                let promise = crate::query_tables(&self.db, &self.query).dyn_into::<Promise>()?;
+                let result = wasm_bindgen_futures::JsFuture::from(promise).await?;
+                let result: Vec<String> = from_value(result)?;
+                Ok(result)
+            }
+
+            async fn run(&self, limit: Option<u32>, column: Option<u32>) -> Result<JsValue, DatabaseError> {
+                if let Some(err) = &self.error {
+                    return Err(err.clone());
+                }
+
+                let promise = crate::exec_sql(
+                    &self.db,
+                    &self.query,
+                    self.get_bindings()?,
+                    self.transaction,
+                    limit,
+                    column,
+                )
+                .dyn_into::<Promise>()?;
                 let result = wasm_bindgen_futures::JsFuture::from(promise).await?;
                 Ok(result)
             }
+
+            // Positional bindings are passed as `$1`, `$2`, ... along with named ones
+            // because sqlite3 accepts either an array or an object, not both.
+            fn get_bindings(&self) -> Result<JsValue, DatabaseError> {
+                if self.named_bindings.is_empty() {
+                    return Ok(self.bindings.iter().collect::<js_sys::Array>().into());
+                }
+
+                let object = js_sys::Object::new();
+
+                for (i, value) in self.bindings.iter().enumerate() {
+                    js_sys::Reflect::set(&object, &JsValue::from_str(&format!("${}", i + 1)), value)?;
+                }
+
+                for (name, value) in &self.named_bindings {
+                    js_sys::Reflect::set(&object, &JsValue::from_str(name), value)?;
+                }
+
+                Ok(object.into())
+            }
+        }
+
+        /// A query made by `query!` or `query_as!`. Rows are deserialized into the record
+        /// inferred from the schema and then mapped to `T`.
+        struct QueryAs<R, T> {
+            query: Query,
+            map: fn(R) -> T,
+        }
+
+        impl<R, T> QueryAs<R, T>
+        where
+            R: DeserializeOwned,
+        {
+            fn new(query: Query, map: fn(R) -> T) -> Self {
+                Self { query, map }
+            }
+
+            pub async fn fetch(&self) -> Result<Vec<T>, DatabaseError> {
+                let result: Vec<R> = self.query.fetch().await?;
+                Ok(result.into_iter().map(self.map).collect())
+            }
+
+            pub async fn fetch_one(&self) -> Result<T, DatabaseError> {
+                let result: R = self.query.fetch_one().await?;
+                Ok((self.map)(result))
+            }
+
+            pub async fn fetch_optional(&self) -> Result<Option<T>, DatabaseError> {
+                let result: Option<R> = self.query.fetch_optional().await?;
+                Ok(result.map(self.map))
+            }
+
+            pub async fn execute(&self) -> Result<JsValue, DatabaseError> {
+                self.query.execute().await
+            }
         }
     }
 }
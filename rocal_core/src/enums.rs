@@ -1 +1,9 @@
+pub mod conflict;
+pub mod database_error;
+pub mod encryption;
 pub mod request_method;
+pub mod routing_mode;
+pub mod storage;
+pub mod sync_error;
+pub mod sync_event;
+pub mod sync_mode;
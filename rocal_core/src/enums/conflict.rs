@@ -0,0 +1,49 @@
+use core::fmt;
+
+use serde::Deserialize;
+
+/// Why a change pulled from another device could not be applied as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// The row was changed on this device too.
+    Data,
+    /// The row to update or delete doesn't exist on this device.
+    NotFound,
+    /// A row with the same primary key was inserted on this device too.
+    Conflict,
+    /// Applying the change would break a constraint.
+    Constraint,
+    /// Applying the change would break a foreign key constraint.
+    ForeignKey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// What to do with a change pulled from another device which conflicts with this device.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictResolution {
+    /// Keeps the row on this device as it is.
+    KeepLocal,
+    /// Overwrites the row on this device with the remote one. Only `Data` and `Conflict`
+    /// can be overwritten; the change is skipped otherwise.
+    TakeRemote,
+    /// Gives up applying any of the pulled changes.
+    Abort,
+}
+
+impl fmt::Display for ConflictResolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictResolution::KeepLocal => write!(f, "local"),
+            ConflictResolution::TakeRemote => write!(f, "remote"),
+            ConflictResolution::Abort => write!(f, "abort"),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use core::fmt;
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+
+const SQLITE_ERROR: i32 = 1;
+const SQLITE_CONSTRAINT: i32 = 19;
+const SQLITE_CONSTRAINT_PRIMARYKEY: i32 = 1555;
+const SQLITE_CONSTRAINT_UNIQUE: i32 = 2067;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DatabaseError {
+    Constraint {
+        code: i32,
+        message: String,
+    },
+    Syntax {
+        code: i32,
+        message: String,
+    },
+    Sqlite {
+        code: i32,
+        message: String,
+    },
+    Deserialization(String),
+    RowNotFound,
+    TooManyRows,
+    OpfsUnavailable,
+    NotADatabase,
+    /// The versions of the migrations which a file to import lacks.
+    MissingMigrations(String),
+    QueryOutsideTransaction,
+    WorkerCrashed(String),
+    Unknown(String),
+}
+
+impl DatabaseError {
+    /// Classifies an error by its (extended) SQLite result code. `SQLITE_ERROR` is used for
+    /// many errors, e.g. a missing table, so only the ones whose message tells a syntax error
+    /// are `Syntax`.
+    pub fn from_sqlite(code: i32, message: &str) -> Self {
+        let syntax = is_syntax_error(message);
+        let message = message.to_string();
+
+        match code & 0xff {
+            SQLITE_CONSTRAINT => DatabaseError::Constraint { code, message },
+            SQLITE_ERROR if syntax => DatabaseError::Syntax { code, message },
+            _ => DatabaseError::Sqlite { code, message },
+        }
+    }
+
+    pub fn get_code(&self) -> Option<i32> {
+        match self {
+            DatabaseError::Constraint { code, .. }
+            | DatabaseError::Syntax { code, .. }
+            | DatabaseError::Sqlite { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    pub fn get_message(&self) -> &str {
+        match self {
+            DatabaseError::Constraint { message, .. }
+            | DatabaseError::Syntax { message, .. }
+            | DatabaseError::Sqlite { message, .. }
+            | DatabaseError::Deserialization(message)
+            | DatabaseError::WorkerCrashed(message)
+            | DatabaseError::Unknown(message) => message,
+            DatabaseError::RowNotFound => "No rows returned by a query that expected a row",
+            DatabaseError::TooManyRows => {
+                "More than one row returned by a query that expected at most one row"
+            }
+            DatabaseError::OpfsUnavailable => {
+                "OPFS not available because of your browser capability."
+            }
+            DatabaseError::NotADatabase => "The file is not a SQLite database.",
+            DatabaseError::MissingMigrations(_) => {
+                "The file lacks migrations of the database. Call migrate before importing it."
+            }
+            DatabaseError::QueryOutsideTransaction => {
+                "A query of the database was made inside its transaction. Use the query of the transaction instead."
+            }
+        }
+    }
+
+    pub fn is_unique_violation(&self) -> bool {
+        matches!(
+            self.get_code(),
+            Some(SQLITE_CONSTRAINT_UNIQUE) | Some(SQLITE_CONSTRAINT_PRIMARYKEY)
+        )
+    }
+}
+
+// The worker prefixes the message of SQLite, e.g. `SQLITE_ERROR: sqlite3 result code 1: near
+// "selct": syntax error`.
+fn is_syntax_error(message: &str) -> bool {
+    let message = message
+        .split_once("result code ")
+        .and_then(|(_, rest)| rest.split_once(": "))
+        .map_or(message, |(_, message)| message);
+
+    message.starts_with("near ") || message.contains("syntax error")
+}
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseError::Constraint { code, message } => {
+                write!(f, "Constraint violation ({}): {}", code, message)
+            }
+            DatabaseError::Syntax { code, message } => {
+                write!(f, "SQL error ({}): {}", code, message)
+            }
+            DatabaseError::Sqlite { code, message } => {
+                write!(f, "SQLite error ({}): {}", code, message)
+            }
+            DatabaseError::Deserialization(message) => {
+                write!(f, "Failed to deserialize a result: {}", message)
+            }
+            DatabaseError::RowNotFound
+            | DatabaseError::TooManyRows
+            | DatabaseError::OpfsUnavailable
+            | DatabaseError::NotADatabase
+            | DatabaseError::QueryOutsideTransaction => write!(f, "{}", self.get_message()),
+            DatabaseError::MissingMigrations(versions) => {
+                write!(f, "{} ({})", self.get_message(), versions)
+            }
+            DatabaseError::WorkerCrashed(message) => {
+                write!(f, "Query worker crashed: {}", message)
+            }
+            DatabaseError::Unknown(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DatabaseError {}
+
+/// Converts an error rejected by the query worker. It is either a plain string or
+/// an object like `{ kind, code, message }`.
+impl From<JsValue> for DatabaseError {
+    fn from(value: JsValue) -> Self {
+        if let Some(message) = value.as_string() {
+            return DatabaseError::Unknown(message);
+        }
+
+        let get = |key: &str| Reflect::get(&value, &JsValue::from_str(key)).ok();
+
+        let kind = get("kind").and_then(|v| v.as_string());
+        let code = get("code").and_then(|v| v.as_f64()).map(|v| v as i32);
+        let message = get("message")
+            .and_then(|v| v.as_string())
+            .unwrap_or_default();
+
+        match (kind.as_deref(), code) {
+            (Some("sqlite"), Some(code)) => DatabaseError::from_sqlite(code, &message),
+            (Some("opfs_unavailable"), _) => DatabaseError::OpfsUnavailable,
+            (Some("not_a_database"), _) => DatabaseError::NotADatabase,
+            (Some("missing_migrations"), _) => DatabaseError::MissingMigrations(message),
+            (Some("worker"), _) => DatabaseError::WorkerCrashed(message),
+            _ => DatabaseError::Unknown(message),
+        }
+    }
+}
+
+impl From<serde_wasm_bindgen::Error> for DatabaseError {
+    fn from(err: serde_wasm_bindgen::Error) -> Self {
+        DatabaseError::Deserialization(err.to_string())
+    }
+}
+
+impl From<DatabaseError> for JsValue {
+    fn from(err: DatabaseError) -> Self {
+        JsValue::from_str(&err.to_string())
+    }
+}
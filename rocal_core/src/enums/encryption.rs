@@ -0,0 +1,31 @@
+use core::fmt;
+
+/// How a database snapshot is protected before it's uploaded, set by `encryption` in `config!`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encryption {
+    /// Uploads the database file as it is.
+    None,
+    /// Encrypts the database file with AES-GCM using a key derived with PBKDF2 from the
+    /// passphrase given by `SyncHandle::set_passphrase`, so the sync server only stores
+    /// ciphertext.
+    Passphrase,
+}
+
+impl Encryption {
+    pub fn from(encryption: &str) -> Option<Self> {
+        match encryption {
+            "none" => Some(Encryption::None),
+            "passphrase" => Some(Encryption::Passphrase),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encryption::None => write!(f, "none"),
+            Encryption::Passphrase => write!(f, "passphrase"),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use core::fmt;
+
+/// How the path of a page appears in its URL, set by `routing_mode` in `config!`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoutingMode {
+    /// Puts the path after `/#`, e.g. `/#/notes/1`, which works on any static file server.
+    Hash,
+    /// Uses the path as it is, e.g. `/notes/1`. The server has to answer every path of the app
+    /// with index.html.
+    History,
+}
+
+impl RoutingMode {
+    pub fn from(mode: &str) -> Option<Self> {
+        match mode {
+            "hash" => Some(RoutingMode::Hash),
+            "history" => Some(RoutingMode::History),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RoutingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoutingMode::Hash => write!(f, "hash"),
+            RoutingMode::History => write!(f, "history"),
+        }
+    }
+}
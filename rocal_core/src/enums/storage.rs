@@ -0,0 +1,49 @@
+use core::fmt;
+
+/// Where the embedded database is kept. It's OPFS whenever the browser supports it, and otherwise
+/// `storage_fallback` in `config!` decides.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Storage {
+    /// The origin private file system, which keeps the database until the site data is cleared.
+    Opfs,
+    /// The memory of the page, which loses the database when the page is closed or reloaded.
+    Memory,
+    /// localStorage through the kvvfs of SQLite, which keeps the database but is limited to a few
+    /// megabytes.
+    LocalStorage,
+    /// sessionStorage through the kvvfs of SQLite, which loses the database when the tab is
+    /// closed.
+    SessionStorage,
+    /// Nowhere, so that every query fails with `DatabaseError::OpfsUnavailable`.
+    None,
+}
+
+impl Storage {
+    pub fn from(storage: &str) -> Option<Self> {
+        match storage {
+            "opfs" => Some(Storage::Opfs),
+            "memory" => Some(Storage::Memory),
+            "local_storage" => Some(Storage::LocalStorage),
+            "session_storage" => Some(Storage::SessionStorage),
+            "none" => Some(Storage::None),
+            _ => None,
+        }
+    }
+
+    /// Whether the database is still there after the browser is restarted.
+    pub fn is_persistent(&self) -> bool {
+        matches!(self, Storage::Opfs | Storage::LocalStorage)
+    }
+}
+
+impl fmt::Display for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Storage::Opfs => write!(f, "opfs"),
+            Storage::Memory => write!(f, "memory"),
+            Storage::LocalStorage => write!(f, "local_storage"),
+            Storage::SessionStorage => write!(f, "session_storage"),
+            Storage::None => write!(f, "none"),
+        }
+    }
+}
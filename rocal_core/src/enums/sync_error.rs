@@ -0,0 +1,67 @@
+use core::fmt;
+
+use serde::Deserialize;
+
+/// Why a sync has failed, reported by `SyncEvent::Failed`. A failed sync is retried with
+/// backoff, or as soon as the browser is back online.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncError {
+    /// The browser is offline.
+    Offline {
+        message: String,
+    },
+    /// The sync server or the storage responded with an error status.
+    Http {
+        status: u16,
+        message: String,
+    },
+    /// The request couldn't reach the sync server.
+    Network {
+        message: String,
+    },
+    /// `encryption: "passphrase"` is set but `SyncHandle::set_passphrase` hasn't been called.
+    PassphraseRequired {
+        message: String,
+    },
+    /// The pulled database couldn't be decrypted, most likely with a wrong passphrase.
+    Decryption {
+        message: String,
+    },
+    /// Reading or writing the database on this device failed.
+    Database {
+        message: String,
+    },
+    Unknown {
+        message: String,
+    },
+}
+
+impl SyncError {
+    pub fn get_status(&self) -> Option<u16> {
+        match self {
+            SyncError::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    pub fn get_message(&self) -> &str {
+        match self {
+            SyncError::Offline { message }
+            | SyncError::Http { message, .. }
+            | SyncError::Network { message }
+            | SyncError::PassphraseRequired { message }
+            | SyncError::Decryption { message }
+            | SyncError::Database { message }
+            | SyncError::Unknown { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.get_message())
+    }
+}
+
+impl std::error::Error for SyncError {}
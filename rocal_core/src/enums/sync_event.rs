@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+use super::sync_error::SyncError;
+
+/// A step of a sync reported to the listeners registered with `SyncHandle::on_event`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SyncEvent {
+    /// A sync has begun. It's followed by one or more of the other events, and ends with
+    /// `Finished` or `Failed`.
+    Started,
+    /// Changes made on other devices have been applied to the database.
+    Pulled,
+    /// Changes made on this device have been sent to the sync server.
+    Pushed,
+    /// There was nothing to sync.
+    UpToDate,
+    /// A sync has succeeded, so `SyncHandle::get_last_synced_at` tells when it finished.
+    Finished,
+    Failed {
+        error: SyncError,
+    },
+}
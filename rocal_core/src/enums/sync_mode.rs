@@ -0,0 +1,34 @@
+use core::fmt;
+
+/// How the embedded database is synced with a sync server, set by `sync_mode` in `config!`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncMode {
+    /// Uploads or downloads the whole database file, whichever is newer.
+    Snapshot,
+    /// Pushes and pulls only the rows which have changed, so edits made offline on several
+    /// devices are merged.
+    Changeset,
+    /// Keeps the database only on this device.
+    None,
+}
+
+impl SyncMode {
+    pub fn from(mode: &str) -> Option<Self> {
+        match mode {
+            "snapshot" => Some(SyncMode::Snapshot),
+            "changeset" => Some(SyncMode::Changeset),
+            "none" => Some(SyncMode::None),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SyncMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncMode::Snapshot => write!(f, "snapshot"),
+            SyncMode::Changeset => write!(f, "changeset"),
+            SyncMode::None => write!(f, "none"),
+        }
+    }
+}
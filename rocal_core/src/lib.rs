@@ -2,24 +2,46 @@
 
 use configuration::{build_config_struct, parse_config};
 use database::build_database_struct;
-use enums::request_method::RequestMethod;
-use migrator::get_migrations;
+use enums::{
+    encryption::Encryption, request_method::RequestMethod, routing_mode::RoutingMode,
+    storage::Storage, sync_mode::SyncMode,
+};
+use migrator::{get_migrations, parse_migration};
 use parsed_action::parse_action;
+use parsed_model::parse_model;
+#[cfg(not(target_arch = "wasm32"))]
+use parsed_query::{parse_query, parse_query_as, ParsedQuery};
 use parsed_route::parse_routes;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{parse_str, Ident, ItemFn, Path};
+use router::Segment;
+#[cfg(not(target_arch = "wasm32"))]
+use schema::{ColumnDescription, Schema};
+#[cfg(not(target_arch = "wasm32"))]
+use syn::Type;
+use syn::{parse::Parser, parse_str, Expr, Ident, ItemFn, Path, Stmt};
 use utils::to_snake_case;
-use workers::db_sync_worker::build_db_sync_worker_struct;
+use workers::{
+    changeset_sync::build_changeset_sync_struct, db_sync_worker::build_db_sync_worker_struct,
+    sync_handle::build_sync_handle_struct,
+};
 
 mod configuration;
+pub mod conflict;
 mod database;
 pub mod enums;
+pub mod migration_file;
 mod migrator;
 mod parsed_action;
+mod parsed_model;
+#[cfg(not(target_arch = "wasm32"))]
+mod parsed_query;
 mod parsed_route;
 pub mod route_handler;
 pub mod router;
+#[cfg(not(target_arch = "wasm32"))]
+mod schema;
+pub mod sql;
 pub mod traits;
 mod utils;
 pub mod workers;
@@ -30,6 +52,14 @@ pub fn start_app(item: TokenStream) -> TokenStream {
 
     let database_struct = build_database_struct();
     let db_sync_worker_struct = build_db_sync_worker_struct();
+    let changeset_sync_struct = build_changeset_sync_struct();
+    let sync_handle_struct = build_sync_handle_struct();
+
+    // Path helpers are made here rather than by route! since the main function would hide them.
+    let route_paths = stmts
+        .iter()
+        .filter_map(get_route_macro_tokens)
+        .map(build_route_paths);
 
     quote! {
         use wasm_bindgen::prelude::*;
@@ -38,22 +68,280 @@ pub fn start_app(item: TokenStream) -> TokenStream {
         #[wasm_bindgen]
         extern "C" {
             #[wasm_bindgen(js_name = execSQL)]
-            fn exec_sql(db: &str, query: &str, bindings: Box<[JsValue]>) -> JsValue;
+            fn exec_sql(
+                db: &str,
+                query: &str,
+                bindings: JsValue,
+                transaction: Option<u32>,
+                limit: Option<u32>,
+                column: Option<u32>,
+            ) -> JsValue;
+
+            #[wasm_bindgen(js_name = newTransactionId)]
+            fn new_transaction_id() -> u32;
+
+            #[wasm_bindgen(js_name = beginTransaction)]
+            fn begin_transaction(db: &str, transaction: u32, untracked: bool) -> JsValue;
+
+            #[wasm_bindgen(js_name = commitTransaction)]
+            fn commit_transaction(db: &str, transaction: u32) -> JsValue;
+
+            #[wasm_bindgen(js_name = rollbackTransaction)]
+            fn rollback_transaction(db: &str, transaction: u32) -> JsValue;
+
+            #[wasm_bindgen(js_name = setStorageFallback)]
+            fn set_storage_fallback(fallback: &str, db: &str);
+
+            #[wasm_bindgen(js_name = databaseStorage)]
+            fn database_storage(db: &str) -> JsValue;
+
+            #[wasm_bindgen(js_name = exportDatabase)]
+            fn export_database(db: &str) -> JsValue;
+
+            #[wasm_bindgen(js_name = importDatabase)]
+            fn import_database(db: &str, bytes: &[u8], migrations: JsValue) -> JsValue;
+
+            #[wasm_bindgen(js_name = queryTables)]
+            fn query_tables(db: &str, query: &str) -> JsValue;
+
+            #[wasm_bindgen(js_name = onDatabaseChange)]
+            fn on_database_change(listener: &Closure<dyn Fn(String, js_sys::Array)>);
+
+            #[wasm_bindgen(js_name = trackChanges)]
+            fn track_changes(db: &str) -> JsValue;
+
+            #[wasm_bindgen(js_name = startChangesetSync)]
+            fn start_changeset_sync(
+                db: &str,
+                app_id: &str,
+                endpoint: &str,
+                resolve_conflict: &Closure<dyn Fn(JsValue) -> JsValue>,
+            );
+
+            #[wasm_bindgen(js_name = startSnapshotSync)]
+            fn start_snapshot_sync(
+                db: &str,
+                app_id: &str,
+                directory_name: &str,
+                file_name: &str,
+                endpoint: &str,
+                encrypted: bool,
+            );
+
+            #[wasm_bindgen(js_name = setSyncPassphrase)]
+            fn set_sync_passphrase(db: &str, passphrase: &str);
+
+            #[wasm_bindgen(js_name = syncPassphrase)]
+            fn sync_passphrase(db: &str) -> Option<String>;
+
+            #[wasm_bindgen(js_name = syncNow)]
+            fn sync_now(db: &str);
+
+            #[wasm_bindgen(js_name = pauseSync)]
+            fn pause_sync(db: &str);
+
+            #[wasm_bindgen(js_name = resumeSync)]
+            fn resume_sync(db: &str);
+
+            #[wasm_bindgen(js_name = isSyncPaused)]
+            fn is_sync_paused(db: &str) -> bool;
+
+            #[wasm_bindgen(js_name = setSyncInterval)]
+            fn set_sync_interval(db: &str, interval: f64);
+
+            #[wasm_bindgen(js_name = lastSyncedAt)]
+            fn last_synced_at(db: &str) -> Option<f64>;
+
+            #[wasm_bindgen(js_name = onSyncEvent)]
+            fn on_sync_event(listener: &Closure<dyn Fn(String, JsValue)>);
         }
 
         #[wasm_bindgen(start)]
         pub async fn run() {
-            #(#stmts)*
+            let config = &crate::CONFIG;
+
+            rocal::rocal_core::router::set_routing_mode(*config.get_routing_mode());
 
-            let db_sync_worker = crate::DbSyncWorker::new(
-                "./js/db_sync_worker.js",
-                crate::ForceType::None
+            // kvvfs has room for only one database in each storage, which is given to the default one.
+            crate::set_storage_fallback(
+                &config.get_storage_fallback().to_string(),
+                &config.get_database().get_name(),
             );
-            db_sync_worker.run();
+
+            let databases: Vec<(std::sync::Arc<crate::Database>, rocal::rocal_core::enums::sync_mode::SyncMode)> =
+                std::iter::once((config.get_database(), *config.get_sync_mode()))
+                    .chain(
+                        config
+                            .get_databases()
+                            .iter()
+                            .map(|database| (database.get_database(), *database.get_sync_mode())),
+                    )
+                    .collect();
+
+            // Changes have to be recorded before anything is written to be pushed later, except
+            // what migrations write since every device runs them by itself.
+            for (db, sync_mode) in &databases {
+                if *sync_mode == rocal::rocal_core::enums::sync_mode::SyncMode::Changeset {
+                    if let Err(err) = db.track_changes().await {
+                        web_sys::console::error_1(&err.into());
+                    }
+                }
+            }
+
+            #(#stmts)*
+
+            for (db, sync_mode) in databases {
+                match sync_mode {
+                    rocal::rocal_core::enums::sync_mode::SyncMode::Snapshot => {
+                        crate::start_snapshot_sync(
+                            &db.get_name(),
+                            config.get_app_id(),
+                            db.get_directory_name(),
+                            db.get_file_name(),
+                            config.get_sync_server_endpoint(),
+                            *config.get_encryption() == rocal::rocal_core::enums::encryption::Encryption::Passphrase,
+                        );
+                    }
+                    rocal::rocal_core::enums::sync_mode::SyncMode::Changeset => {
+                        crate::ChangesetSync::new(db).run();
+                    }
+                    rocal::rocal_core::enums::sync_mode::SyncMode::None => (),
+                }
+            }
         }
 
         #database_struct
         #db_sync_worker_struct
+        #changeset_sync_struct
+        #sync_handle_struct
+        #(#route_paths)*
+    }
+}
+
+fn get_route_macro_tokens(stmt: &Stmt) -> Option<TokenStream> {
+    let mac = match stmt {
+        Stmt::Macro(stmt) => &stmt.mac,
+        Stmt::Expr(Expr::Macro(expr), _) => &expr.mac,
+        _ => return None,
+    };
+
+    if mac.path.segments.last()?.ident == "route" {
+        Some(mac.tokens.clone())
+    } else {
+        None
+    }
+}
+
+/// Makes `routes::<name>_path` for each route named with `as`, which takes one argument for each
+/// `<param>` segment of the path, an `i64` for an `int` one, and percent-encodes it.
+pub fn build_route_paths(item: TokenStream) -> TokenStream {
+    // An invalid route! is reported by route! itself.
+    let routes = match parse_routes(item) {
+        Ok(routes) => routes,
+        Err(_) => return quote!(),
+    };
+
+    let mut paths = vec![];
+
+    for route in &routes {
+        let (name, method, path) = match (route.get_name(), route.get_method(), route.get_path()) {
+            (Some(name), Some(method), Some(path)) => (name, method, path),
+            _ => continue,
+        };
+
+        let fn_name = Ident::new(&format!("{}_path", name), name.span());
+        let doc = format!("The path of `{} {}`.", method, path);
+
+        let mut params = vec![];
+        let mut args = vec![];
+        let mut optional_params = vec![];
+        let mut optional_args = vec![];
+        let mut format = vec![];
+
+        for segment in path.split('/') {
+            let (param, constraint, optional, encode) = match Segment::parse(segment) {
+                Segment::Static(segment) => {
+                    format.push(segment.replace('{', "{{").replace('}', "}}"));
+                    continue;
+                }
+                Segment::Param {
+                    name,
+                    constraint,
+                    optional,
+                } => (name, constraint, optional, quote!(encode_segment)),
+                Segment::Wildcard(name) => (name, None, false, quote!(encode_path)),
+            };
+
+            let param = match parse_str::<Ident>(param) {
+                Ok(param) => param,
+                Err(_) => {
+                    return syn::Error::new(
+                        name.span(),
+                        format!(
+                            "<{}> in {} can't be an argument of {}",
+                            param, path, fn_name
+                        ),
+                    )
+                    .to_compile_error()
+                }
+            };
+
+            let ty = match constraint {
+                Some("int") => quote!(i64),
+                _ => quote!(impl std::fmt::Display),
+            };
+            let encode = quote!(rocal::rocal_core::router::#encode);
+
+            // Optional params can only be at the end of a path, so they are appended after
+            // formatting the rest.
+            if optional {
+                optional_args.push(quote!(#param.map(#encode)));
+                optional_params.push(quote!(#param: Option<#ty>));
+            } else {
+                format.push("{}".to_string());
+                args.push(quote!(#encode(#param)));
+                params.push(quote!(#param: #ty));
+            }
+        }
+
+        let format = format.join("/");
+
+        if optional_params.is_empty() {
+            paths.push(quote! {
+                #[doc = #doc]
+                pub fn #fn_name(#(#params),*) -> String {
+                    format!(#format, #(#args),*)
+                }
+            });
+        } else {
+            paths.push(quote! {
+                #[doc = #doc]
+                pub fn #fn_name(#(#params,)* #(#optional_params),*) -> String {
+                    let mut path = format!(#format, #(#args),*);
+
+                    for param in [#(#optional_args),*].into_iter().map_while(|param| param) {
+                        path.push('/');
+                        path.push_str(&param);
+                    }
+
+                    if path.is_empty() {
+                        path.push('/');
+                    }
+
+                    path
+                }
+            });
+        }
+    }
+
+    if paths.is_empty() {
+        return quote!();
+    }
+
+    quote! {
+        pub mod routes {
+            #(#paths)*
+        }
     }
 }
 
@@ -172,6 +460,78 @@ pub fn build_config(item: TokenStream) -> TokenStream {
         .get_database_file_name()
         .clone()
         .unwrap_or(String::new());
+    let sync_mode = to_sync_mode_tokens(&config.get_sync_mode().unwrap_or(SyncMode::Snapshot));
+    let databases = config.get_databases().iter().map(|database| {
+        let name = database.get_name();
+        let directory_name = database
+            .get_directory_name()
+            .clone()
+            .unwrap_or(database_directory_name.clone());
+        let file_name = database
+            .get_file_name()
+            .clone()
+            .unwrap_or(format!("{}.sqlite3", name));
+        let sync_mode = to_sync_mode_tokens(&database.get_sync_mode().unwrap_or(SyncMode::None));
+
+        (name, directory_name, file_name, sync_mode)
+    });
+
+    let mut paths = vec![format!(
+        "{}/{}",
+        database_directory_name, database_file_name
+    )];
+
+    for (name, directory_name, file_name, _) in databases.clone() {
+        let path = format!("{}/{}", directory_name, file_name);
+
+        if paths.contains(&path) {
+            return syn::Error::new(
+                Span::call_site(),
+                format!("{} is stored in {} which another database uses", name, path),
+            )
+            .to_compile_error();
+        }
+
+        paths.push(path);
+    }
+
+    let databases = databases.map(|(name, directory_name, file_name, sync_mode)| {
+        quote! {
+            crate::NamedDatabase::new(
+                #name.to_string(),
+                std::sync::Arc::new(Database::new(
+                    #directory_name.to_string(),
+                    #file_name.to_string(),
+                )),
+                #sync_mode,
+            )
+        }
+    });
+
+    let encryption = match config.get_encryption() {
+        Some(Encryption::Passphrase) => {
+            quote! { rocal::rocal_core::enums::encryption::Encryption::Passphrase }
+        }
+        _ => quote! { rocal::rocal_core::enums::encryption::Encryption::None },
+    };
+
+    let routing_mode = match config.get_routing_mode() {
+        Some(RoutingMode::History) => {
+            quote! { rocal::rocal_core::enums::routing_mode::RoutingMode::History }
+        }
+        _ => quote! { rocal::rocal_core::enums::routing_mode::RoutingMode::Hash },
+    };
+
+    let storage_fallback = match config.get_storage_fallback() {
+        Some(Storage::LocalStorage) => {
+            quote! { rocal::rocal_core::enums::storage::Storage::LocalStorage }
+        }
+        Some(Storage::SessionStorage) => {
+            quote! { rocal::rocal_core::enums::storage::Storage::SessionStorage }
+        }
+        Some(Storage::None) => quote! { rocal::rocal_core::enums::storage::Storage::None },
+        _ => quote! { rocal::rocal_core::enums::storage::Storage::Memory },
+    };
 
     quote! {
         #config_struct
@@ -180,15 +540,28 @@ pub fn build_config(item: TokenStream) -> TokenStream {
             crate::Configuration::new(
                 #app_id.to_string(),
                 #sync_server_endpoint.to_string(),
+                #sync_mode,
+                #encryption,
+                #storage_fallback,
+                #routing_mode,
                 std::sync::Arc::new(Database::new(
                     #database_directory_name.to_string(),
                     #database_file_name.to_string(),
                 )),
+                vec![#(#databases),*],
             )
         });
     }
 }
 
+fn to_sync_mode_tokens(sync_mode: &SyncMode) -> TokenStream {
+    match sync_mode {
+        SyncMode::Snapshot => quote! { rocal::rocal_core::enums::sync_mode::SyncMode::Snapshot },
+        SyncMode::Changeset => quote! { rocal::rocal_core::enums::sync_mode::SyncMode::Changeset },
+        SyncMode::None => quote! { rocal::rocal_core::enums::sync_mode::SyncMode::None },
+    }
+}
+
 pub fn build_action(item: TokenStream) -> TokenStream {
     let ast: ItemFn = syn::parse2(item).unwrap();
 
@@ -215,12 +588,16 @@ pub fn build_action(item: TokenStream) -> TokenStream {
             }
         } else {
             quote! {
-                let #name = args.get(#name_str).expect(&format!("{} is required", #name_str));
+                // A path may leave out an optional param, which only an `Option` argument can
+                // go without.
+                let #name = match args.get(#name_str) {
+                    Some(#name) => #name,
+                    None => return false,
+                };
             }
         };
 
         let ty = arg.get_ty();
-        let ty_str = ty.to_string();
 
         result = if ty == "String" || ty == "str" {
             quote!(#result)
@@ -243,7 +620,12 @@ pub fn build_action(item: TokenStream) -> TokenStream {
                 quote! {
                     #result
 
-                    let #name = #name.parse::<#ty>().expect(&format!("{} cannot be parsed as {}", #name_str, #ty_str));
+                    // A value the type can't hold, e.g. an overflowing or negative number, makes
+                    // the page not found instead of a panic.
+                    let #name = match #name.parse::<#ty>() {
+                        Ok(#name) => #name,
+                        Err(_) => return false,
+                    };
                 }
             }
         };
@@ -254,28 +636,324 @@ pub fn build_action(item: TokenStream) -> TokenStream {
     let stmts = &ast.block.stmts;
 
     quote! {
-        pub async fn #fn_name(&self, args: std::collections::HashMap<String, String>) {
+        pub async fn #fn_name(&self, args: std::collections::HashMap<String, String>) -> bool {
             #(#build_args)*
 
-            #(#stmts)*
+            async {
+                #(#stmts)*
+            }
+            .await;
+
+            true
+        }
+    }
+}
+
+pub fn build_model(item: TokenStream) -> TokenStream {
+    let model = match parse_model(item) {
+        Ok(model) => model,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    let name = model.get_name();
+    let table = model.get_table();
+    let primary_key = model.get_primary_key();
+    let primary_key_str = primary_key.to_string();
+    let columns = model.get_columns();
+    let column_names: Vec<String> = columns.iter().map(|column| column.to_string()).collect();
+
+    let insert_columns: Vec<&Ident> = columns
+        .iter()
+        .filter(|column| !(*model.get_auto_increment() && *column == primary_key))
+        .collect();
+    let insert_column_names: Vec<String> = insert_columns
+        .iter()
+        .map(|column| column.to_string())
+        .collect();
+
+    let update_columns: Vec<&Ident> = columns
+        .iter()
+        .filter(|column| *column != primary_key)
+        .collect();
+    let update_column_names: Vec<String> = update_columns
+        .iter()
+        .map(|column| column.to_string())
+        .collect();
+
+    let select_query = format!("select {} from {}", column_names.join(", "), table);
+    let find_query = format!("{} where {} = $1;", select_query, primary_key_str);
+    let all_query = format!("{};", select_query);
+    let insert_query = format!(
+        "insert into {} ({}) values ({}) returning {};",
+        table,
+        insert_column_names.join(", "),
+        insert_column_names
+            .iter()
+            .map(|column| format!(":{}", column))
+            .collect::<Vec<String>>()
+            .join(", "),
+        column_names.join(", ")
+    );
+    let update_query = format!(
+        "update {} set {} where {} = :{};",
+        table,
+        update_column_names
+            .iter()
+            .map(|column| format!("{} = :{}", column, column))
+            .collect::<Vec<String>>()
+            .join(", "),
+        primary_key_str,
+        primary_key_str
+    );
+    let delete_query = format!("delete from {} where {} = $1;", table, primary_key_str);
+
+    let update = if update_columns.is_empty() {
+        quote!(Ok(()))
+    } else {
+        quote! {
+            database
+                .query(#update_query)
+                #(.bind_named(#update_column_names, &self.#update_columns))*
+                .bind_named(#primary_key_str, &self.#primary_key)
+                .execute()
+                .await?;
+
+            Ok(())
+        }
+    };
+
+    quote! {
+        impl #name {
+            pub const TABLE_NAME: &'static str = #table;
+            pub const PRIMARY_KEY: &'static str = #primary_key_str;
+            pub const COLUMNS: &'static [&'static str] = &[#(#column_names),*];
+
+            pub async fn find<K>(
+                database: &crate::Database,
+                key: K,
+            ) -> Result<Option<Self>, rocal::rocal_core::enums::database_error::DatabaseError>
+            where
+                K: rocal::rocal_core::traits::ToSql,
+            {
+                database.query(#find_query).bind(key).fetch_optional().await
+            }
+
+            pub async fn all(
+                database: &crate::Database,
+            ) -> Result<Vec<Self>, rocal::rocal_core::enums::database_error::DatabaseError> {
+                database.query(#all_query).fetch().await
+            }
+
+            pub async fn insert(
+                &self,
+                database: &crate::Database,
+            ) -> Result<Self, rocal::rocal_core::enums::database_error::DatabaseError> {
+                database
+                    .query(#insert_query)
+                    #(.bind_named(#insert_column_names, &self.#insert_columns))*
+                    .fetch_one()
+                    .await
+            }
+
+            pub async fn update(
+                &self,
+                database: &crate::Database,
+            ) -> Result<(), rocal::rocal_core::enums::database_error::DatabaseError> {
+                #update
+            }
+
+            pub async fn delete(
+                &self,
+                database: &crate::Database,
+            ) -> Result<(), rocal::rocal_core::enums::database_error::DatabaseError> {
+                database
+                    .query(#delete_query)
+                    .bind(&self.#primary_key)
+                    .execute()
+                    .await?;
+
+                Ok(())
+            }
         }
     }
 }
 
 pub fn run_migration(item: TokenStream) -> TokenStream {
-    let query = match get_migrations(&item) {
-        Ok(query) => query,
+    let migration = match parse_migration.parse2(item) {
+        Ok(migration) => migration,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let migrations = match get_migrations(migration.get_path()) {
+        Ok(migrations) => migrations,
         Err(err) => return err.to_compile_error().into(),
     };
 
-    if !query.is_empty() {
+    let database = match migration.get_database() {
+        Some(name) => quote! { CONFIG.database(#name) },
+        None => quote! { CONFIG.get_database() },
+    };
+
+    if !migrations.is_empty() {
+        let versions = migrations.iter().map(|migration| migration.get_version());
+        let queries = migrations.iter().map(|migration| migration.get_query());
+
         quote! {
-            match CONFIG.get_database().query(#query).execute().await {
+            match #database.migrate(&[#((#versions, #queries)),*]).await {
                 Ok(_) => (),
-                Err(err) => web_sys::console::error_1(&err),
+                Err(err) => web_sys::console::error_1(&err.into()),
             }
         }
     } else {
         quote!()
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn build_query(item: TokenStream) -> TokenStream {
+    match parse_query.parse2(item) {
+        Ok(query) => expand_query(&query).unwrap_or_else(|err| err.to_compile_error()),
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn build_query_as(item: TokenStream) -> TokenStream {
+    match parse_query_as.parse2(item) {
+        Ok(query) => expand_query(&query).unwrap_or_else(|err| err.to_compile_error()),
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn expand_query(query: &ParsedQuery) -> Result<TokenStream, syn::Error> {
+    let sql = query.get_sql();
+    let migrations = get_migrations(
+        &query
+            .get_migrations()
+            .as_ref()
+            .map(|path| quote! { #path })
+            .unwrap_or_default(),
+    )?;
+    let schema = Schema::new(&migrations).map_err(|err| syn::Error::new(sql.span(), err))?;
+    let description = schema
+        .describe(&sql.value())
+        .map_err(|err| syn::Error::new(sql.span(), err))?;
+
+    let args = query.get_args();
+
+    if *description.get_parameter_count() != args.len() {
+        return Err(syn::Error::new(
+            sql.span(),
+            format!(
+                "expected {} parameter(s) but {} given",
+                description.get_parameter_count(),
+                args.len()
+            ),
+        ));
+    }
+
+    let mut columns: Vec<String> = vec![];
+    let mut fields: Vec<Ident> = vec![];
+    let mut tys: Vec<TokenStream> = vec![];
+
+    for column in description.get_columns() {
+        let (field, ty) = infer_field(column).map_err(|err| syn::Error::new(sql.span(), err))?;
+
+        columns.push(column.get_name().to_string());
+        fields.push(field);
+        tys.push(ty);
+    }
+
+    let database = query.get_database();
+
+    let map = match query.get_target() {
+        Some(target) => quote! { |record: Record| #target { #(#fields: record.#fields),* } },
+        None => quote! { |record: Record| record },
+    };
+
+    Ok(quote! {
+        {
+            #[derive(serde::Deserialize)]
+            #[allow(dead_code, non_snake_case)]
+            struct Record {
+                #(
+                    #[serde(rename = #columns)]
+                    #fields: #tys,
+                )*
+            }
+
+            let mut query = (#database).query(#sql);
+            #(query.bind(#args);)*
+            crate::QueryAs::new(query, #map)
+        }
+    })
+}
+
+/// A column can override what is inferred with its alias, as in `count(*) as "count!: u32"`.
+/// `!` marks it as not null, `?` as nullable, and the type after `:` replaces the inferred one.
+#[cfg(not(target_arch = "wasm32"))]
+fn infer_field(column: &ColumnDescription) -> Result<(Ident, TokenStream), String> {
+    let (name, ty) = match column.get_name().split_once(':') {
+        Some((name, ty)) => (name.trim(), Some(ty.trim())),
+        None => (column.get_name().trim(), None),
+    };
+
+    let (name, not_null) = if let Some(name) = name.strip_suffix('!') {
+        (name, Some(true))
+    } else if let Some(name) = name.strip_suffix('?') {
+        (name, Some(false))
+    } else {
+        (name, *column.get_not_null())
+    };
+
+    let field: Ident = parse_str(name).map_err(|_| {
+        format!(
+            "column `{}` needs an alias which is a valid Rust identifier, e.g. `as \"name\"`",
+            column.get_name()
+        )
+    })?;
+
+    let ty: TokenStream = match ty {
+        Some(ty) => parse_str::<Type>(ty)
+            .map(|ty| quote! { #ty })
+            .map_err(|_| format!("`{}` is not a valid type for column `{}`", ty, name))?,
+        None => column
+            .get_decl_type()
+            .as_deref()
+            .map(infer_type)
+            .ok_or_else(|| {
+                format!(
+                    "the type of column `{}` cannot be inferred, give it with an alias e.g. `as \"{}: i64\"`",
+                    name, name
+                )
+            })?,
+    };
+
+    if not_null.unwrap_or(false) {
+        Ok((field, ty))
+    } else {
+        Ok((field, quote! { Option<#ty> }))
+    }
+}
+
+/// Follows the rules SQLite uses to determine the affinity of a declared type, except that
+/// `DATE`, `DATETIME` and `TIME`, which have NUMERIC affinity in SQLite, are deliberately read as
+/// text, since SQLite's date and time functions like `current_timestamp` store them as text.
+#[cfg(not(target_arch = "wasm32"))]
+fn infer_type(decl_type: &str) -> TokenStream {
+    let decl_type = decl_type.to_uppercase();
+
+    if decl_type.contains("INT") {
+        quote! { i64 }
+    } else if ["CHAR", "CLOB", "TEXT", "DATE", "TIME"]
+        .iter()
+        .any(|ty| decl_type.contains(ty))
+    {
+        quote! { String }
+    } else if decl_type.contains("BLOB") || decl_type.is_empty() {
+        quote! { Vec<u8> }
+    } else {
+        quote! { f64 }
+    }
+}
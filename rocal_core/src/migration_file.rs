@@ -0,0 +1,130 @@
+use std::{fs, path::Path};
+
+pub const UP_MARKER: &str = "-- migrate:up";
+pub const DOWN_MARKER: &str = "-- migrate:down";
+
+/// Returns the version of a migration from its file name, e.g. `20250101000000` for
+/// `20250101000000-create-notes-table.sql`.
+pub fn parse_version(file_name: &str) -> Option<String> {
+    let version: String = file_name
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+
+    if version.is_empty() {
+        None
+    } else {
+        Some(version)
+    }
+}
+
+/// Splits a migration into the queries which apply it and, if it has a `-- migrate:down` line,
+/// the ones which roll it back.
+pub fn split_sections(contents: &str) -> (String, Option<String>) {
+    let mut up = String::new();
+    let mut down: Option<String> = None;
+
+    for line in contents.lines() {
+        let marker = line.trim();
+
+        if marker == UP_MARKER {
+            continue;
+        }
+
+        if marker == DOWN_MARKER {
+            down = Some(String::new());
+            continue;
+        }
+
+        let section = match down.as_mut() {
+            Some(down) => down,
+            None => &mut up,
+        };
+
+        section.push_str(line);
+        section.push('\n');
+    }
+
+    (up, down)
+}
+
+/// A migration file read from a migrations directory.
+pub struct MigrationFile {
+    version: String,
+    file_name: String,
+    up: String,
+    down: Option<String>,
+}
+
+impl MigrationFile {
+    pub fn get_version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn get_file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn get_up(&self) -> &str {
+        &self.up
+    }
+
+    /// `None` when the file has no `-- migrate:down` line.
+    pub fn get_down(&self) -> Option<&str> {
+        self.down.as_deref()
+    }
+}
+
+/// Reads the `.sql` files in a migrations directory in the order of their versions. A file
+/// without a version or with the same version as another one is an error, so that `migrate!`
+/// and `rocal migrate` agree on the migrations of an app.
+pub fn read_migration_files(dir: &Path) -> Result<Vec<MigrationFile>, String> {
+    let dir =
+        fs::read_dir(dir).map_err(|err| format!("Failed to read {}: {}", dir.display(), err))?;
+
+    let mut entries: Vec<_> = dir.filter_map(Result::ok).collect();
+
+    entries.sort_by_key(|entry| entry.path());
+
+    let mut result: Vec<MigrationFile> = vec![];
+
+    for entry in entries {
+        let path = entry.path();
+
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "sql") {
+            continue;
+        }
+
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        let version = match parse_version(&file_name) {
+            Some(version) => version,
+            None => {
+                return Err(format!(
+                    "{} should start with a timestamp e.g. <timestamp>-<name>.sql",
+                    file_name
+                ));
+            }
+        };
+
+        if result.iter().any(|migration| migration.version == version) {
+            return Err(format!(
+                "{} has the same version as another migration",
+                file_name
+            ));
+        }
+
+        let contents =
+            fs::read_to_string(&path).map_err(|_| format!("{} cannot be opened", file_name))?;
+        let (up, down) = split_sections(&contents);
+
+        result.push(MigrationFile {
+            version,
+            file_name,
+            up,
+            down,
+        });
+    }
+
+    Ok(result)
+}
@@ -1,48 +1,102 @@
 use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
 use std::{
-    env, fs,
+    env,
     path::{Path, PathBuf},
 };
-use syn::{spanned::Spanned, LitStr};
+use syn::{parse::ParseStream, spanned::Spanned, Ident, LitStr, Token};
 
-pub fn get_migrations(item: &TokenStream) -> Result<String, syn::Error> {
-    let path_name: LitStr = if item.is_empty() {
-        LitStr::new("db/migrations", item.span())
-    } else {
-        syn::parse2(item.clone())?
-    };
+use crate::migration_file::read_migration_files;
 
-    let path = resolve_path(&path_name.value(), item.span())?;
+pub struct Migration {
+    version: String,
+    query: String,
+}
+
+impl Migration {
+    pub fn get_version(&self) -> &str {
+        &self.version
+    }
 
-    let mut result = String::new();
+    pub fn get_query(&self) -> &str {
+        &self.query
+    }
+}
+
+pub struct ParsedMigration {
+    path: TokenStream,
+    database: Option<LitStr>,
+}
+
+impl ParsedMigration {
+    pub fn get_path(&self) -> &TokenStream {
+        &self.path
+    }
+
+    /// The name of a database declared under `databases` in `config!`.
+    pub fn get_database(&self) -> &Option<LitStr> {
+        &self.database
+    }
+}
 
-    if let Ok(dir) = fs::read_dir(&path) {
-        let mut entries: Vec<_> = dir.filter_map(Result::ok).collect();
+/// `migrate!("db/migrations")` or `migrate!("db/cache/migrations", database: "cache")`
+pub fn parse_migration(input: ParseStream) -> Result<ParsedMigration, syn::Error> {
+    if input.is_empty() {
+        return Ok(ParsedMigration {
+            path: TokenStream::new(),
+            database: None,
+        });
+    }
 
-        entries.sort_by_key(|entry| entry.path());
+    let path: LitStr = input.parse()?;
 
-        for entry in entries {
-            let path = entry.path();
+    let database = if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+        let key: Ident = input.parse()?;
 
-            if path.is_file() {
-                if let Ok(contents) = fs::read_to_string(&path) {
-                    result += &contents;
-                } else {
-                    return Err(syn::Error::new(
-                        item.span(),
-                        format!("{} cannot be opened", entry.file_name().to_str().unwrap()),
-                    ));
-                }
-            }
+        if key != "database" {
+            return Err(syn::Error::new(key.span(), "expected database: \"<name>\""));
         }
 
-        Ok(result)
+        input.parse::<Token![:]>()?;
+        let name: LitStr = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+
+        Some(name)
+    } else {
+        None
+    };
+
+    Ok(ParsedMigration {
+        path: path.to_token_stream(),
+        database,
+    })
+}
+
+pub fn get_migrations(item: &TokenStream) -> Result<Vec<Migration>, syn::Error> {
+    let path_name: LitStr = if item.is_empty() {
+        LitStr::new("db/migrations", item.span())
     } else {
-        Err(syn::Error::new(
+        syn::parse2(item.clone())?
+    };
+
+    let path = resolve_path(&path_name.value(), item.span())?;
+
+    if !path.is_dir() {
+        return Err(syn::Error::new(
             item.span(),
             format!("{} not found", path_name.value()),
-        ))
+        ));
     }
+
+    let files = read_migration_files(&path).map_err(|err| syn::Error::new(item.span(), err))?;
+
+    Ok(files
+        .into_iter()
+        .map(|file| Migration {
+            version: file.get_version().to_string(),
+            query: file.get_up().to_string(),
+        })
+        .collect())
 }
 
 fn resolve_path(path: impl AsRef<Path>, span: Span) -> syn::Result<PathBuf> {
@@ -0,0 +1,129 @@
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Fields, Ident, LitBool, LitStr, Type};
+
+use crate::utils::to_snake_case;
+
+#[derive(Debug)]
+pub struct ParsedModel {
+    name: Ident,
+    table: String,
+    primary_key: Ident,
+    columns: Vec<Ident>,
+    auto_increment: bool,
+}
+
+impl ParsedModel {
+    pub fn get_name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn get_table(&self) -> &str {
+        &self.table
+    }
+
+    pub fn get_primary_key(&self) -> &Ident {
+        &self.primary_key
+    }
+
+    pub fn get_columns(&self) -> &Vec<Ident> {
+        &self.columns
+    }
+
+    pub fn get_auto_increment(&self) -> &bool {
+        &self.auto_increment
+    }
+}
+
+pub fn parse_model(item: TokenStream) -> Result<ParsedModel, syn::Error> {
+    let ast: DeriveInput = syn::parse2(item)?;
+
+    let fields = match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &ast.ident,
+                    "Model can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                "Model can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut table: Option<String> = None;
+    let mut primary_key: Option<String> = None;
+    let mut auto_increment: Option<bool> = None;
+
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("rocal"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("primary_key") {
+                primary_key = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("auto_increment") {
+                auto_increment = Some(meta.value()?.parse::<LitBool>()?.value());
+                Ok(())
+            } else {
+                Err(meta
+                    .error("unknown property key: expected table, primary_key, or auto_increment"))
+            }
+        })?;
+    }
+
+    // Only an `s` is added, so e.g. `Category` needs `table = "categories"`.
+    let table = table.unwrap_or_else(|| format!("{}s", to_snake_case(&ast.ident.to_string())));
+    let primary_key = primary_key.unwrap_or_else(|| String::from("id"));
+
+    let columns: Vec<Ident> = fields
+        .iter()
+        .filter_map(|field| field.ident.clone())
+        .collect();
+
+    let primary_key_field = fields
+        .iter()
+        .find(|field| {
+            field
+                .ident
+                .as_ref()
+                .is_some_and(|ident| ident == &primary_key)
+        })
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &ast.ident,
+                format!("{} should have a field named {}", ast.ident, primary_key),
+            )
+        })?;
+
+    let auto_increment = auto_increment.unwrap_or_else(|| is_integer(&primary_key_field.ty));
+
+    Ok(ParsedModel {
+        name: ast.ident.clone(),
+        table,
+        primary_key: primary_key_field.ident.clone().expect("named field"),
+        columns,
+        auto_increment,
+    })
+}
+
+fn is_integer(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.path.segments.last().is_some_and(|segment| {
+            matches!(
+                segment.ident.to_string().as_str(),
+                "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize"
+            )
+        }),
+        _ => false,
+    }
+}
@@ -0,0 +1,89 @@
+use syn::{parse::ParseStream, Expr, Ident, LitStr, Path, Token};
+
+#[derive(Debug)]
+pub struct ParsedQuery {
+    target: Option<Path>,
+    database: Expr,
+    sql: LitStr,
+    args: Vec<Expr>,
+    migrations: Option<LitStr>,
+}
+
+impl ParsedQuery {
+    pub fn get_target(&self) -> &Option<Path> {
+        &self.target
+    }
+
+    pub fn get_database(&self) -> &Expr {
+        &self.database
+    }
+
+    pub fn get_sql(&self) -> &LitStr {
+        &self.sql
+    }
+
+    pub fn get_args(&self) -> &Vec<Expr> {
+        &self.args
+    }
+
+    /// The migrations which the schema is built from, as given to `migrate!`.
+    pub fn get_migrations(&self) -> &Option<LitStr> {
+        &self.migrations
+    }
+}
+
+/// `query!(database, "select ...", args...)` or
+/// `query!(database, "select ...", args..., migrations: "db/cache/migrations")`
+pub fn parse_query(input: ParseStream) -> Result<ParsedQuery, syn::Error> {
+    parse_query_args(input, None)
+}
+
+/// `query_as!(Target, database, "select ...", args...)`
+pub fn parse_query_as(input: ParseStream) -> Result<ParsedQuery, syn::Error> {
+    let target: Path = input.parse()?;
+    input.parse::<Token![,]>()?;
+
+    parse_query_args(input, Some(target))
+}
+
+fn parse_query_args(input: ParseStream, target: Option<Path>) -> Result<ParsedQuery, syn::Error> {
+    let database: Expr = input.parse()?;
+    input.parse::<Token![,]>()?;
+
+    let sql: LitStr = input.parse()?;
+
+    let mut args = vec![];
+    let mut migrations = None;
+
+    while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+        if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+            let key: Ident = input.parse()?;
+
+            if key != "migrations" {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected migrations: \"<path>\"",
+                ));
+            }
+
+            input.parse::<Token![:]>()?;
+            migrations = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+            break;
+        }
+
+        args.push(input.parse()?);
+    }
+
+    if !input.is_empty() {
+        return Err(input.error("migrations should be given after the arguments"));
+    }
+
+    Ok(ParsedQuery {
+        target,
+        database,
+        sql,
+        args,
+        migrations,
+    })
+}
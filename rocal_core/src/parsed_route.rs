@@ -1,12 +1,16 @@
 use proc_macro2::TokenStream;
 use syn::{
     braced,
+    ext::IdentExt,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
     Ident, LitStr, Token,
 };
 
-use crate::enums::request_method::RequestMethod;
+use crate::{
+    enums::request_method::RequestMethod,
+    router::{constraint_regex, Segment},
+};
 
 mod kw {
     syn::custom_keyword!(get);
@@ -20,7 +24,7 @@ mod kw {
 }
 
 pub fn parse_routes(item: TokenStream) -> Result<Vec<ParsedRoute>, syn::Error> {
-    let routes: ParsedRoutes = syn::parse(item.into())?;
+    let routes: ParsedRoutes = syn::parse2(item)?;
 
     Ok(routes.0)
 }
@@ -32,9 +36,23 @@ impl Parse for ParsedRoutes {
     fn parse(input: ParseStream) -> Result<Self, syn::Error> {
         let routes = Punctuated::<ParsedRoute, Token!(,)>::parse_terminated(&input)?;
         let mut result: Vec<ParsedRoute> = vec![];
-        routes.into_iter().for_each(|route| {
+
+        for route in routes {
+            if let Some(name) = route.get_name() {
+                if result
+                    .iter()
+                    .any(|other| other.get_name() == &Some(name.clone()))
+                {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!("{} is the name of another route", name),
+                    ));
+                }
+            }
+
             result.push(route);
-        });
+        }
+
         Ok(ParsedRoutes(result))
     }
 }
@@ -46,6 +64,7 @@ pub struct ParsedRoute {
     controller: Option<Ident>,
     action: Option<Ident>,
     view: Option<Ident>,
+    name: Option<Ident>,
 }
 
 impl ParsedRoute {
@@ -69,6 +88,10 @@ impl ParsedRoute {
         self.view = Some(view);
     }
 
+    pub fn set_name(&mut self, name: Ident) {
+        self.name = Some(name);
+    }
+
     pub fn get_method(&self) -> &Option<RequestMethod> {
         &self.method
     }
@@ -88,6 +111,11 @@ impl ParsedRoute {
     pub fn get_view(&self) -> &Option<Ident> {
         &self.view
     }
+
+    /// The name given by `as`, which names the path helper of the route.
+    pub fn get_name(&self) -> &Option<Ident> {
+        &self.name
+    }
 }
 
 impl Parse for ParsedRoute {
@@ -128,11 +156,14 @@ impl Parse for ParsedRoute {
 
         route.set_method(method);
 
-        let path = input
+        let path: LitStr = input
             .parse()
-            .map(|v: LitStr| v.value())
             .map_err(|_| syn::Error::new(input.span(), "Path is required"))?;
 
+        validate_path(&path)?;
+
+        let path = path.value();
+
         let _: Token!(=>) = input.parse().map_err(|_| {
             syn::Error::new(
                 input.span(),
@@ -154,6 +185,8 @@ impl Parse for ParsedRoute {
                 route.set_action(kv.value);
             } else if kv.key == "view" {
                 route.set_view(kv.value);
+            } else if kv.key == "as" {
+                route.set_name(kv.value);
             }
         });
 
@@ -161,6 +194,62 @@ impl Parse for ParsedRoute {
     }
 }
 
+fn validate_path(path: &LitStr) -> Result<(), syn::Error> {
+    let value = path.value();
+    let segments: Vec<Segment> = value.split('/').map(Segment::parse).collect();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let rest = &segments[i + 1..];
+
+        match segment {
+            Segment::Wildcard("") => {
+                return Err(syn::Error::new(
+                    path.span(),
+                    format!("* in {} should be followed by a name", value),
+                ));
+            }
+            Segment::Wildcard(name) if !rest.is_empty() => {
+                return Err(syn::Error::new(
+                    path.span(),
+                    format!("*{} should be the last segment of {}", name, value),
+                ));
+            }
+            Segment::Param {
+                name,
+                constraint: Some(constraint),
+                ..
+            } if constraint_regex(constraint).is_err() => {
+                return Err(syn::Error::new(
+                    path.span(),
+                    format!(
+                        "{} of <{}> in {} should be int or a regex",
+                        constraint, name, value
+                    ),
+                ));
+            }
+            Segment::Param {
+                name,
+                optional: true,
+                ..
+            } if rest
+                .iter()
+                .any(|s| !matches!(s, Segment::Param { optional: true, .. })) =>
+            {
+                return Err(syn::Error::new(
+                    path.span(),
+                    format!(
+                        "<{}>? in {} should be followed only by optional params",
+                        name, value
+                    ),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
 struct KeyValue {
     key: String,
@@ -169,18 +258,22 @@ struct KeyValue {
 
 impl Parse for KeyValue {
     fn parse(input: ParseStream) -> Result<Self, syn::Error> {
-        let key = input.parse().map(|v: Ident| v.to_string()).map_err(|_| {
-            syn::Error::new(
-                input.span(),
-                "should have property keys within curly braces",
-            )
-        })?;
+        let key = input
+            .call(Ident::parse_any)
+            .map(|v| v.to_string())
+            .map_err(|_| {
+                syn::Error::new(
+                    input.span(),
+                    "should have property keys within curly braces",
+                )
+            })?;
 
         let _: Token!(:) = input.parse().map_err(|_| {
             syn::Error::new(input.span(), "prop key and value should be separated by :")
         })?;
 
-        let value: Ident = if key == "controller" || key == "action" || key == "view" {
+        let value: Ident = if key == "controller" || key == "action" || key == "view" || key == "as"
+        {
             input
                 .parse()
                 .map_err(|_| syn::Error::new(input.span(), "Property requires a value"))
@@ -5,7 +5,7 @@ use url::Url;
 use web_sys::window;
 
 use crate::enums::request_method::RequestMethod;
-use crate::router::Router;
+use crate::router::{to_path, Router};
 
 pub struct RouteHandler {
     router: Rc<RefCell<Router>>,
@@ -43,14 +43,14 @@ impl RouteHandler {
             }
         };
 
-        let path = url.fragment().unwrap_or_else(|| "/");
+        let path = to_path(&url);
 
-        if !self
+        let resolving = self
             .router
             .borrow()
-            .resolve(RequestMethod::Get, path, None)
-            .await
-        {
+            .resolve(RequestMethod::Get, &path, None);
+
+        if !resolving.await {
             (self.not_found)();
         }
     }
@@ -1,17 +1,223 @@
-use std::{collections::HashMap, future::Future, pin::Pin};
+use std::{cell::Cell, collections::HashMap, fmt::Display, future::Future, pin::Pin, rc::Rc};
 
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
 use regex::Regex;
 use url::Url;
 use wasm_bindgen::JsValue;
 use web_sys::{console, window};
 
-use crate::enums::request_method::RequestMethod;
+use crate::enums::{request_method::RequestMethod, routing_mode::RoutingMode};
 
-type Action = Box<dyn Fn(HashMap<String, String>) -> Pin<Box<dyn Future<Output = ()>>>>;
+/// Runs a page with the params of its path. It resolves to `false` when the params don't fit
+/// the arguments of the action, e.g. `99999999999999999999` for an `i64`.
+type Action = Box<dyn Fn(HashMap<String, String>) -> Pin<Box<dyn Future<Output = bool>>>>;
 
+/// The characters which are encoded in a segment of a path: the ones of the path segment set of
+/// the URL standard plus `%`, so that a param is decoded back as it was.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+thread_local! {
+    static ROUTING_MODE: Cell<RoutingMode> = const { Cell::new(RoutingMode::Hash) };
+}
+
+/// A segment of a route, e.g. `notes`, `<note_id>`, `<note_id:int>`, `<note_id>?` or `*path`.
+#[derive(Debug, PartialEq)]
+pub enum Segment<'a> {
+    Static(&'a str),
+    /// Matches one segment, which has to match the constraint if any. An optional one may be
+    /// left out at the end of a path.
+    Param {
+        name: &'a str,
+        constraint: Option<&'a str>,
+        optional: bool,
+    },
+    /// Matches the rest of a path, which has to have at least one segment.
+    Wildcard(&'a str),
+}
+
+impl<'a> Segment<'a> {
+    pub fn parse(segment: &'a str) -> Self {
+        let (param, optional) = match segment.strip_suffix('?') {
+            Some(param) => (param, true),
+            None => (segment, false),
+        };
+
+        if let Some(param) = param.strip_prefix('<').and_then(|p| p.strip_suffix('>')) {
+            let (name, constraint) = match param.split_once(':') {
+                Some((name, constraint)) => (name, Some(constraint)),
+                None => (param, None),
+            };
+
+            Segment::Param {
+                name,
+                constraint,
+                optional,
+            }
+        } else if let Some(name) = segment.strip_prefix('*') {
+            Segment::Wildcard(name)
+        } else {
+            Segment::Static(segment)
+        }
+    }
+}
+
+/// Makes the regex of a param constraint, which is either `int` or a regex the whole segment
+/// has to match.
+pub fn constraint_regex(constraint: &str) -> Result<Regex, regex::Error> {
+    let pattern = match constraint {
+        "int" => "-?[0-9]+",
+        pattern => pattern,
+    };
+
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+struct Param {
+    name: String,
+    constraint: Option<String>,
+    regex: Option<Regex>,
+    node: Node,
+}
+
+impl Param {
+    fn accepts(&self, segment: &str) -> bool {
+        !segment.is_empty()
+            && self
+                .regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(segment))
+    }
+}
+
+#[derive(Default)]
 struct Node {
-    children: HashMap<String, Node>,
-    action: Option<Action>,
+    statics: HashMap<String, Node>,
+    params: Vec<Param>,
+    wildcards: Vec<(String, Node)>,
+    action: Option<Rc<Action>>,
+}
+
+impl Node {
+    fn insert(&mut self, segments: &[&str], action: Rc<Action>) {
+        let mut ptr = self;
+
+        for segment in segments {
+            ptr = match Segment::parse(segment) {
+                Segment::Static(segment) => ptr.statics.entry(segment.to_string()).or_default(),
+                Segment::Param {
+                    name, constraint, ..
+                } => Self::param(&mut ptr.params, name, constraint),
+                Segment::Wildcard(name) => Self::wildcard(&mut ptr.wildcards, name),
+            };
+        }
+
+        ptr.action = Some(action);
+    }
+
+    // Constrained params are kept before the others so that they are tried first.
+    fn param<'a>(params: &'a mut Vec<Param>, name: &str, constraint: Option<&str>) -> &'a mut Node {
+        let index = match params
+            .iter()
+            .position(|param| param.name == name && param.constraint.as_deref() == constraint)
+        {
+            Some(index) => index,
+            None => {
+                let index = match constraint {
+                    Some(_) => params
+                        .iter()
+                        .take_while(|param| param.constraint.is_some())
+                        .count(),
+                    None => params.len(),
+                };
+
+                let regex = constraint.map(|constraint| {
+                    constraint_regex(constraint).unwrap_or_else(|err| {
+                        panic!(
+                            "{} is not a valid constraint of <{}>: {}",
+                            constraint, name, err
+                        )
+                    })
+                });
+
+                params.insert(
+                    index,
+                    Param {
+                        name: name.to_string(),
+                        constraint: constraint.map(|constraint| constraint.to_string()),
+                        regex,
+                        node: Node::default(),
+                    },
+                );
+                index
+            }
+        };
+
+        &mut params[index].node
+    }
+
+    fn wildcard<'a>(children: &'a mut Vec<(String, Node)>, name: &str) -> &'a mut Node {
+        let index = match children.iter().position(|(key, _)| key == name) {
+            Some(index) => index,
+            None => {
+                children.push((name.to_string(), Node::default()));
+                children.len() - 1
+            }
+        };
+
+        &mut children[index].1
+    }
+
+    // Static segments are tried before params, and params before wildcards, going back to the
+    // next candidate when a segment doesn't meet a constraint or the rest of the path doesn't
+    // match under one.
+    fn find(&self, segments: &[&str], args: &mut HashMap<String, String>) -> Option<&Rc<Action>> {
+        let (segment, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => return self.action.as_ref(),
+        };
+
+        if let Some(action) = self
+            .statics
+            .get(*segment)
+            .and_then(|child| child.find(rest, args))
+        {
+            return Some(action);
+        }
+
+        let decoded = percent_decode_str(segment).decode_utf8_lossy();
+
+        for param in self.params.iter().filter(|param| param.accepts(&decoded)) {
+            if let Some(action) = param.node.find(rest, args) {
+                args.insert(param.name.to_string(), decoded.to_string());
+                return Some(action);
+            }
+        }
+
+        let remaining = segments.join("/");
+
+        if !remaining.is_empty() {
+            for (name, child) in &self.wildcards {
+                if let Some(action) = child.action.as_ref() {
+                    let remaining = percent_decode_str(&remaining).decode_utf8_lossy();
+                    args.insert(name.to_string(), remaining.to_string());
+                    return Some(action);
+                }
+            }
+        }
+
+        None
+    }
 }
 
 pub struct Router {
@@ -23,55 +229,68 @@ impl Router {
 
     pub fn new() -> Self {
         Router {
-            root: Node {
-                children: HashMap::new(),
-                action: None,
-            },
+            root: Node::default(),
         }
     }
 
+    /// Registers an action for a route. A route ending with optional params is registered for
+    /// each of its lengths, e.g. `/items` and `/items/<id>` for `/items/<id>?`.
     pub fn register(&mut self, method: RequestMethod, route: &str, action: Action) {
-        let mut ptr = &mut self.root;
-
-        if !ptr.children.contains_key(&method.to_string()) {
-            ptr.children.insert(
-                method.to_string(),
-                Node {
-                    children: HashMap::new(),
-                    action: None,
-                },
-            );
-        }
+        let action = Rc::new(action);
+        let segments: Vec<&str> = route.split("/").collect();
+        let required = segments
+            .iter()
+            .rposition(|segment| {
+                !matches!(
+                    Segment::parse(segment),
+                    Segment::Param { optional: true, .. }
+                )
+            })
+            .map_or(0, |index| index + 1);
 
-        ptr = ptr.children.get_mut(&method.to_string()).unwrap();
+        let node = self.root.statics.entry(method.to_string()).or_default();
 
-        for s in route.split("/") {
-            if !ptr.children.contains_key(s) {
-                ptr.children.insert(
-                    s.to_string(),
-                    Node {
-                        children: HashMap::new(),
-                        action: None,
-                    },
-                );
+        for len in required..=segments.len() {
+            match &segments[..len] {
+                // Without its optional segments, `/<id>?` is `/`.
+                [""] => node.insert(&["", ""], action.clone()),
+                segments => node.insert(segments, action.clone()),
             }
+        }
+    }
 
-            ptr = ptr.children.get_mut(s).unwrap();
+    /// Runs the action of a route. The action is found right away, so the returned future
+    /// doesn't borrow the router and the router can be borrowed again while it runs.
+    pub fn resolve(
+        &self,
+        method: RequestMethod,
+        route: &str,
+        action_args: Option<HashMap<String, String>>,
+    ) -> impl Future<Output = bool> + 'static {
+        let running = self
+            .find(method, route, action_args.unwrap_or_default())
+            .map(|(action, action_args)| action(action_args));
+
+        async move {
+            match running {
+                Some(running) => running.await,
+                None => false,
+            }
         }
+    }
 
-        ptr.action = Some(action);
+    /// Whether a route is registered for the path, without running its action.
+    pub fn recognizes(&self, method: RequestMethod, route: &str) -> bool {
+        self.find(method, route, HashMap::new()).is_some()
     }
 
-    pub async fn resolve(
+    fn find(
         &self,
         method: RequestMethod,
         route: &str,
-        action_args: Option<HashMap<String, String>>,
-    ) -> bool {
+        mut action_args: HashMap<String, String>,
+    ) -> Option<(&Rc<Action>, HashMap<String, String>)> {
         let mut route = route.to_string();
-        let path_param_regex: Regex = Regex::new(r"^<(?<key>.+)>$").unwrap();
-
-        let mut action_args: HashMap<String, String> = action_args.unwrap_or(HashMap::new());
 
         if let Ok(url) = Url::parse(&format!("{}{}", Self::HOST, route)) {
             for (k, v) in url.query_pairs() {
@@ -80,48 +299,26 @@ impl Router {
             route = url.path().to_string();
         }
 
-        let mut ptr = &self.root;
-
-        if !ptr.children.contains_key(&method.to_string()) {
-            return false;
-        }
-
-        ptr = ptr.children.get(&method.to_string()).unwrap();
-
-        for s in route.split("/") {
-            if !ptr.children.contains_key(s) {
-                if let Some(param) = ptr
-                    .children
-                    .keys()
-                    .find(|key| path_param_regex.is_match(key))
-                {
-                    let caps = path_param_regex.captures(&param).unwrap();
-                    action_args.insert(caps["key"].to_string(), s.to_string());
-                    ptr = ptr.children.get(param).unwrap();
-                    continue;
-                } else {
-                    return false;
-                }
-            }
-
-            ptr = ptr.children.get(s).unwrap();
-        }
+        let segments: Vec<&str> = route.split("/").collect();
+        let action = self
+            .root
+            .statics
+            .get(&method.to_string())?
+            .find(&segments, &mut action_args)?;
 
-        if let Some(action) = &ptr.action {
-            action(action_args).await;
-            true
-        } else {
-            false
-        }
+        Some((action, action_args))
     }
 
-    pub async fn redirect(&self, path: &str) -> bool {
-        let result = self.resolve(RequestMethod::Get, path, None).await;
+    /// Runs the action of a path and pushes the path onto the history. Like `resolve`, the
+    /// returned future doesn't borrow the router.
+    pub fn redirect(&self, path: &str) -> impl Future<Output = bool> + 'static {
+        let resolving = self.resolve(RequestMethod::Get, path, None);
+        let path = path.to_string();
 
-        if !result {
-            return false;
-        }
+        async move { resolving.await && Self::push_state(&path) }
+    }
 
+    fn push_state(path: &str) -> bool {
         let win = if let Some(win) = window() {
             win
         } else {
@@ -135,7 +332,7 @@ impl Router {
                 return false;
             };
 
-        let abs = format!("{}/#{}", origin, path);
+        let abs = format!("{}{}", origin, to_url(path));
 
         if let Err(err) = history.push_state_with_url(&JsValue::NULL, "", Some(&abs)) {
             console::error_1(&err);
@@ -146,10 +343,94 @@ impl Router {
     }
 }
 
+/// Makes the `href` of a link to a page of the app, or of a `remote` link which the browser
+/// follows by itself. A remote link should also have a `data-remote` attribute so that its
+/// clicks aren't routed by the router.
 pub fn link_to(path: &str, remote: bool) -> String {
     if remote {
-        format!("{}", path)
+        path.to_string()
     } else {
-        format!("/#{}", path)
+        to_url(path)
+    }
+}
+
+/// Percent-encodes a param of a path so that it stays one segment, e.g. `a%2Fb` for `a/b`.
+pub fn encode_segment(param: impl Display) -> String {
+    utf8_percent_encode(&param.to_string(), SEGMENT).to_string()
+}
+
+/// Percent-encodes each segment of the rest of a path matched by a wildcard, keeping the `/`
+/// between them.
+pub fn encode_path(path: impl Display) -> String {
+    path.to_string()
+        .split('/')
+        .map(encode_segment)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn set_routing_mode(routing_mode: RoutingMode) {
+    ROUTING_MODE.with(|mode| mode.set(routing_mode));
+}
+
+pub fn get_routing_mode() -> RoutingMode {
+    ROUTING_MODE.with(|mode| mode.get())
+}
+
+/// Makes the URL of a page from its path, e.g. `/#/notes` in the hash routing mode.
+pub fn to_url(path: &str) -> String {
+    match get_routing_mode() {
+        RoutingMode::Hash => format!("/#{}", path),
+        RoutingMode::History => path.to_string(),
+    }
+}
+
+/// Returns the path of the page of the app which a link on `location` points to, or `None` if
+/// the browser should follow the link by itself, e.g. to another site, a file, or an anchor on
+/// the same page.
+pub fn to_link_path(href: &Url, location: &Url) -> Option<String> {
+    if href.origin() != location.origin() {
+        return None;
+    }
+
+    match (get_routing_mode(), href.fragment()) {
+        (RoutingMode::Hash, Some(fragment)) if fragment.starts_with('/') => {
+            Some(fragment.to_string())
+        }
+        (RoutingMode::Hash, _) => None,
+        (RoutingMode::History, Some(fragment))
+            if !fragment.starts_with('/')
+                && href.path() == location.path()
+                && href.query() == location.query() =>
+        {
+            None
+        }
+        (RoutingMode::History, _) => {
+            let is_file = href
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .is_some_and(|segment| segment.contains('.'));
+
+            if is_file {
+                None
+            } else {
+                Some(to_path(href))
+            }
+        }
+    }
+}
+
+/// Returns the path of the page which a URL points to. In the history routing mode, a URL made
+/// in the hash routing mode still points to its page.
+pub fn to_path(url: &Url) -> String {
+    let fragment = url.fragment().filter(|fragment| fragment.starts_with('/'));
+
+    match (get_routing_mode(), fragment) {
+        (RoutingMode::Hash, _) => url.fragment().unwrap_or("/").to_string(),
+        (RoutingMode::History, Some(fragment)) if url.path() == "/" => fragment.to_string(),
+        (RoutingMode::History, _) => match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        },
     }
 }
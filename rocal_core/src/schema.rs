@@ -0,0 +1,109 @@
+use rusqlite::Connection;
+
+use crate::migrator::Migration;
+
+/// An in-memory copy of the embedded database, built by applying migrations while a macro
+/// expands so that queries can be checked against it.
+pub struct Schema {
+    conn: Connection,
+}
+
+#[derive(Debug)]
+pub struct QueryDescription {
+    parameter_count: usize,
+    columns: Vec<ColumnDescription>,
+}
+
+impl QueryDescription {
+    pub fn get_parameter_count(&self) -> &usize {
+        &self.parameter_count
+    }
+
+    pub fn get_columns(&self) -> &Vec<ColumnDescription> {
+        &self.columns
+    }
+}
+
+#[derive(Debug)]
+pub struct ColumnDescription {
+    name: String,
+    decl_type: Option<String>,
+    not_null: Option<bool>,
+}
+
+impl ColumnDescription {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_decl_type(&self) -> &Option<String> {
+        &self.decl_type
+    }
+
+    /// `None` when the column is an expression rather than a column of a table.
+    pub fn get_not_null(&self) -> &Option<bool> {
+        &self.not_null
+    }
+}
+
+impl Schema {
+    pub fn new(migrations: &[Migration]) -> Result<Self, String> {
+        let conn = Connection::open_in_memory().map_err(|err| err.to_string())?;
+
+        for migration in migrations {
+            conn.execute_batch(migration.get_query()).map_err(|err| {
+                format!(
+                    "Failed to apply the migration {}: {}",
+                    migration.get_version(),
+                    err
+                )
+            })?;
+        }
+
+        Ok(Schema { conn })
+    }
+
+    pub fn describe(&self, sql: &str) -> Result<QueryDescription, String> {
+        let stmt = self.conn.prepare(sql).map_err(|err| err.to_string())?;
+
+        let columns = stmt
+            .columns_with_metadata()
+            .iter()
+            .map(|column| {
+                let origin = column.table_name().zip(column.origin_name());
+
+                let metadata = origin.and_then(|(table, origin)| {
+                    self.conn.column_metadata(None, table, origin).ok().map(
+                        |(decl_type, _, not_null, primary_key, _)| {
+                            let decl_type = decl_type.map(|ty| ty.to_string_lossy().to_string());
+                            let is_rowid = primary_key
+                                && decl_type
+                                    .as_deref()
+                                    .is_some_and(|ty| ty.eq_ignore_ascii_case("integer"));
+
+                            (decl_type, not_null || is_rowid)
+                        },
+                    )
+                });
+
+                match metadata {
+                    Some((decl_type, not_null)) => ColumnDescription {
+                        name: column.name().to_string(),
+                        decl_type,
+                        not_null: Some(not_null),
+                    },
+                    None => ColumnDescription {
+                        name: column.name().to_string(),
+                        decl_type: None,
+                        not_null: None,
+                    },
+                }
+            })
+            .collect();
+
+        Ok(QueryDescription {
+            parameter_count: stmt.parameter_count(),
+            columns,
+        })
+    }
+}
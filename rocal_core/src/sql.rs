@@ -0,0 +1,44 @@
+/// Returns the names of the named parameters of SQL in order, e.g. `:name`, `@name` or `$1`,
+/// skipping string literals, quoted identifiers and comments.
+pub fn get_param_names(sql: &str) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut names: Vec<String> = vec![];
+    let mut i = 0;
+
+    let skip_to = |from: usize, end: &[char]| -> usize {
+        let mut j = from;
+
+        while j < chars.len() && !chars[j..].starts_with(end) {
+            j += 1;
+        }
+
+        j + end.len()
+    };
+
+    while i < chars.len() {
+        match chars[i] {
+            // A quote in a literal is doubled, which is read as two literals in a row.
+            quote @ ('\'' | '"' | '`') => i = skip_to(i + 1, &[quote]),
+            '[' => i = skip_to(i + 1, &[']']),
+            '-' if chars.get(i + 1) == Some(&'-') => i = skip_to(i + 2, &['\n']),
+            '/' if chars.get(i + 1) == Some(&'*') => i = skip_to(i + 2, &['*', '/']),
+            ':' | '@' | '$' => {
+                let start = i;
+                i += 1;
+
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+
+                let name: String = chars[start..i].iter().collect();
+
+                if 1 < name.len() && !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    names
+}
@@ -1,13 +1,26 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
 use url::Url;
-use wasm_bindgen::{closure::Closure, JsCast};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 use wasm_bindgen_futures::spawn_local;
-use web_sys::{window, Document, Event, FormData, HtmlFormElement};
+use web_sys::{
+    window, Document, Element, Event, FormData, HtmlAnchorElement, HtmlFormElement, MouseEvent,
+};
 
-use crate::{enums::request_method::RequestMethod, router::Router};
+use crate::{
+    enums::request_method::RequestMethod,
+    router::{to_link_path, Router},
+};
 
 pub type SharedRouter = Rc<RefCell<Router>>;
 
+thread_local! {
+    static LINK_LISTENER_REGISTERED: Cell<bool> = const { Cell::new(false) };
+}
+
 pub trait Controller {
     type View;
     fn new(router: SharedRouter, view: Self::View) -> Self;
@@ -27,6 +40,7 @@ pub trait Template {
     fn render(&self, data: Self::Data) {
         self.render_html(&self.body(data));
         self.register_forms();
+        self.register_links();
     }
 
     fn render_html(&self, html: &str) {
@@ -63,6 +77,49 @@ pub trait Template {
         }
     }
 
+    /// Routes clicks on links to pages of the app through the router instead of letting the
+    /// browser load them. The listener is put on the document only once since it outlives
+    /// every render.
+    fn register_links(&self) {
+        if LINK_LISTENER_REGISTERED.with(|registered| registered.get()) {
+            return;
+        }
+
+        let doc = match self.get_document() {
+            Some(doc) => doc,
+            None => return,
+        };
+
+        let router_for_closure = self.router().clone();
+
+        let closure = Closure::wrap(Box::new(move |e: Event| {
+            let path = match get_link_path(&e) {
+                Some(path) => path,
+                None => return,
+            };
+
+            if !router_for_closure
+                .borrow()
+                .recognizes(RequestMethod::Get, &path)
+            {
+                return;
+            }
+
+            e.prevent_default();
+
+            let redirecting = router_for_closure.borrow().redirect(&path);
+            spawn_local(async move {
+                redirecting.await;
+            });
+        }) as Box<dyn FnMut(Event)>);
+
+        doc.add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
+            .expect("Failed to add a click event listener");
+        closure.forget();
+
+        LINK_LISTENER_REGISTERED.with(|registered| registered.set(true));
+    }
+
     fn get_document(&self) -> Option<Document> {
         window()?.document()
     }
@@ -113,18 +170,17 @@ pub trait Template {
             }
 
             if let Ok(url) = Url::parse(&element.action()) {
-                let router = router_for_closure.clone();
+                let method = RequestMethod::from(
+                    &element
+                        .get_attribute("method")
+                        .unwrap_or(String::from("post")),
+                );
+                let resolving = router_for_closure
+                    .borrow()
+                    .resolve(method, url.path(), Some(args));
+
                 spawn_local(async move {
-                    let method = RequestMethod::from(
-                        &element
-                            .get_attribute("method")
-                            .unwrap_or(String::from("post")),
-                    );
-
-                    router
-                        .borrow()
-                        .resolve(method, url.path(), Some(args))
-                        .await;
+                    resolving.await;
                 });
             }
         }) as Box<dyn FnMut(Event)>);
@@ -134,3 +190,158 @@ pub trait Template {
         closure.forget();
     }
 }
+
+/// Returns the path of the page which a clicked link points to. Clicks which open the link
+/// elsewhere, i.e. with a modifier key, with the middle button or in another `target`, are left
+/// to the browser, as are links with `data-remote` or `download`.
+fn get_link_path(e: &Event) -> Option<String> {
+    let mouse_event = e.dyn_ref::<MouseEvent>()?;
+
+    if e.default_prevented()
+        || mouse_event.button() != 0
+        || mouse_event.meta_key()
+        || mouse_event.ctrl_key()
+        || mouse_event.shift_key()
+        || mouse_event.alt_key()
+    {
+        return None;
+    }
+
+    let anchor: HtmlAnchorElement = e
+        .target()?
+        .dyn_into::<Element>()
+        .ok()?
+        .closest("a[href]")
+        .ok()??
+        .dyn_into()
+        .ok()?;
+
+    let target = anchor.target();
+
+    if (!target.is_empty() && target != "_self")
+        || anchor.has_attribute("data-remote")
+        || anchor.has_attribute("download")
+    {
+        return None;
+    }
+
+    let href = Url::parse(&anchor.href()).ok()?;
+    let location = Url::parse(&window()?.location().href().ok()?).ok()?;
+
+    to_link_path(&href, &location)
+}
+
+/// Converts a value into a JS value which the query worker binds as the matching SQLite type.
+pub trait ToSql {
+    fn to_sql(&self) -> JsValue;
+}
+
+impl<T: ToSql + ?Sized> ToSql for &T {
+    fn to_sql(&self) -> JsValue {
+        (**self).to_sql()
+    }
+}
+
+impl<T: ToSql> ToSql for Option<T> {
+    fn to_sql(&self) -> JsValue {
+        match self {
+            Some(value) => value.to_sql(),
+            None => JsValue::NULL,
+        }
+    }
+}
+
+impl ToSql for JsValue {
+    fn to_sql(&self) -> JsValue {
+        self.clone()
+    }
+}
+
+impl ToSql for str {
+    fn to_sql(&self) -> JsValue {
+        JsValue::from_str(self)
+    }
+}
+
+impl ToSql for String {
+    fn to_sql(&self) -> JsValue {
+        JsValue::from_str(self)
+    }
+}
+
+impl ToSql for bool {
+    fn to_sql(&self) -> JsValue {
+        JsValue::from(*self as i32)
+    }
+}
+
+macro_rules! impl_to_sql_for_number {
+    ( $( $ty:ty ),* ) => {
+        $(
+            impl ToSql for $ty {
+                fn to_sql(&self) -> JsValue {
+                    JsValue::from(*self as f64)
+                }
+            }
+        )*
+    };
+}
+
+impl_to_sql_for_number!(i8, i16, i32, u8, u16, u32, f32, f64);
+
+// 64-bit integers are passed as BigInt so that ids above 2^53 don't lose precision. `u64` and
+// `usize` aren't bindable, since SQLite can't store a value above `i64::MAX`; convert them with
+// `i64::try_from` first.
+macro_rules! impl_to_sql_for_bigint {
+    ( $( $ty:ty ),* ) => {
+        $(
+            impl ToSql for $ty {
+                fn to_sql(&self) -> JsValue {
+                    js_sys::BigInt::from(*self).into()
+                }
+            }
+        )*
+    };
+}
+
+impl_to_sql_for_bigint!(i64, isize);
+
+impl ToSql for [u8] {
+    fn to_sql(&self) -> JsValue {
+        js_sys::Uint8Array::from(self).into()
+    }
+}
+
+impl ToSql for Vec<u8> {
+    fn to_sql(&self) -> JsValue {
+        self.as_slice().to_sql()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToSql for chrono::NaiveDate {
+    fn to_sql(&self) -> JsValue {
+        JsValue::from_str(&self.format("%Y-%m-%d").to_string())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToSql for chrono::NaiveTime {
+    fn to_sql(&self) -> JsValue {
+        JsValue::from_str(&self.format("%H:%M:%S%.f").to_string())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToSql for chrono::NaiveDateTime {
+    fn to_sql(&self) -> JsValue {
+        JsValue::from_str(&self.format("%Y-%m-%d %H:%M:%S%.f").to_string())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> ToSql for chrono::DateTime<Tz> {
+    fn to_sql(&self) -> JsValue {
+        self.naive_utc().to_sql()
+    }
+}
@@ -1 +1,3 @@
+pub mod changeset_sync;
 pub mod db_sync_worker;
+pub mod sync_handle;
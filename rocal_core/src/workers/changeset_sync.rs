@@ -0,0 +1,54 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub fn build_changeset_sync_struct() -> TokenStream {
+    quote! {
+        /// Syncs the database with `sync_mode: "changeset"`, pushing and pulling only the rows
+        /// which have changed.
+        pub struct ChangesetSync {
+            database: std::sync::Arc<crate::Database>,
+        }
+
+        impl ChangesetSync {
+            pub fn new(database: std::sync::Arc<crate::Database>) -> Self {
+                ChangesetSync { database }
+            }
+
+            pub fn run(&self) {
+                let config = &crate::CONFIG;
+                let name = self.database.get_name();
+
+                let resolver = Closure::<dyn Fn(JsValue) -> JsValue>::new(move |conflict: JsValue| {
+                    resolve_conflict(&name, conflict)
+                });
+
+                crate::start_changeset_sync(
+                    &self.database.get_name(),
+                    config.get_app_id(),
+                    config.get_sync_server_endpoint(),
+                    &resolver,
+                );
+
+                resolver.forget();
+            }
+        }
+
+        // Returns undefined to let the query worker apply the default strategy.
+        fn resolve_conflict(db: &str, conflict: JsValue) -> JsValue {
+            let resolver = CONFLICT_RESOLVERS.with(|resolvers| resolvers.borrow().get(db).cloned());
+
+            let resolver = match resolver {
+                Some(resolver) => resolver,
+                None => return JsValue::UNDEFINED,
+            };
+
+            match serde_wasm_bindgen::from_value::<rocal::rocal_core::conflict::Conflict>(conflict) {
+                Ok(conflict) => JsValue::from_str(&resolver(&conflict).to_string()),
+                Err(err) => {
+                    web_sys::console::error_1(&err.into());
+                    JsValue::UNDEFINED
+                }
+            }
+        }
+    }
+}
@@ -24,6 +24,8 @@ pub fn build_db_sync_worker_struct() -> TokenStream {
             directory_name: &'a str,
             file_name: &'a str,
             endpoint: &'a str,
+            encrypted: bool,
+            passphrase: Option<String>,
             force: &'a str,
         }
 
@@ -52,6 +54,9 @@ pub fn build_db_sync_worker_struct() -> TokenStream {
                         directory_name: db.get_directory_name(),
                         file_name: db.get_file_name(),
                         endpoint: config.get_sync_server_endpoint(),
+                        encrypted: *config.get_encryption()
+                            == rocal::rocal_core::enums::encryption::Encryption::Passphrase,
+                        passphrase: crate::sync_passphrase(&db.get_name()),
                         force,
                     };
 
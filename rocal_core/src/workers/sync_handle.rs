@@ -0,0 +1,140 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub fn build_sync_handle_struct() -> TokenStream {
+    quote! {
+        /// Controls the background sync of a database, which runs every 30 seconds by default,
+        /// and tells how it's going.
+        #[derive(Clone)]
+        pub struct SyncHandle {
+            db: String,
+        }
+
+        impl SyncHandle {
+            pub fn new(db: String) -> Self {
+                SyncHandle { db }
+            }
+
+            /// Syncs right away instead of waiting for the next interval, even while paused.
+            /// Does nothing if a sync is already running.
+            pub fn sync_now(&self) {
+                crate::sync_now(&self.db);
+            }
+
+            /// Stops syncing every interval until `resume` is called.
+            pub fn pause(&self) {
+                crate::pause_sync(&self.db);
+            }
+
+            pub fn resume(&self) {
+                crate::resume_sync(&self.db);
+            }
+
+            pub fn is_paused(&self) -> bool {
+                crate::is_sync_paused(&self.db)
+            }
+
+            pub fn set_interval(&self, interval: std::time::Duration) {
+                crate::set_sync_interval(&self.db, interval.as_millis() as f64);
+            }
+
+            /// Sets the passphrase which the key to encrypt the database is derived from, when
+            /// `encryption: "passphrase"` is set in `config!`. It isn't stored anywhere, so it has
+            /// to be set again after the app is reloaded, and the database isn't synced until then.
+            pub fn set_passphrase(&self, passphrase: &str) {
+                crate::set_sync_passphrase(&self.db, passphrase);
+            }
+
+            /// When the last sync finished without errors, in milliseconds since the Unix epoch.
+            pub fn get_last_synced_at(&self) -> Option<f64> {
+                crate::last_synced_at(&self.db)
+            }
+
+            /// Calls `listener` with each event of the syncs of this database until the returned
+            /// subscription is dropped.
+            #[must_use]
+            pub fn on_event<F>(&self, listener: F) -> SyncSubscription
+            where
+                F: Fn(&rocal::rocal_core::enums::sync_event::SyncEvent) + 'static,
+            {
+                SYNC_EVENT_LISTENER.with(|sync_event_listener| {
+                    sync_event_listener.get_or_init(|| {
+                        let sync_event_listener = Closure::new(notify_sync_listeners);
+                        crate::on_sync_event(&sync_event_listener);
+                        sync_event_listener
+                    });
+                });
+
+                let id = NEXT_SYNC_LISTENER_ID.with(|next| {
+                    let id = next.get();
+                    next.set(id + 1);
+                    id
+                });
+
+                SYNC_LISTENERS.with(|listeners| {
+                    listeners.borrow_mut().push(SyncListener {
+                        id,
+                        db: self.db.clone(),
+                        listener: std::rc::Rc::new(listener),
+                    });
+                });
+
+                SyncSubscription { id }
+            }
+        }
+
+        struct SyncListener {
+            id: u32,
+            db: String,
+            listener: std::rc::Rc<dyn Fn(&rocal::rocal_core::enums::sync_event::SyncEvent)>,
+        }
+
+        thread_local! {
+            static SYNC_LISTENERS: std::cell::RefCell<Vec<SyncListener>> = std::cell::RefCell::new(vec![]);
+            static NEXT_SYNC_LISTENER_ID: std::cell::Cell<u32> = std::cell::Cell::new(0);
+            static SYNC_EVENT_LISTENER: std::cell::OnceCell<Closure<dyn Fn(String, JsValue)>> =
+                std::cell::OnceCell::new();
+        }
+
+        fn notify_sync_listeners(db: String, event: JsValue) {
+            let event = match serde_wasm_bindgen::from_value::<rocal::rocal_core::enums::sync_event::SyncEvent>(event) {
+                Ok(event) => event,
+                Err(err) => {
+                    web_sys::console::error_1(&err.into());
+                    return;
+                }
+            };
+
+            let listeners: Vec<std::rc::Rc<dyn Fn(&rocal::rocal_core::enums::sync_event::SyncEvent)>> =
+                SYNC_LISTENERS.with(|listeners| {
+                    listeners
+                        .borrow()
+                        .iter()
+                        .filter(|listener| listener.db == db)
+                        .map(|listener| listener.listener.clone())
+                        .collect()
+                });
+
+            for listener in listeners {
+                listener(&event);
+            }
+        }
+
+        /// Stops the listener registered with `SyncHandle::on_event` when dropped.
+        pub struct SyncSubscription {
+            id: u32,
+        }
+
+        impl SyncSubscription {
+            pub fn unsubscribe(self) {}
+        }
+
+        impl Drop for SyncSubscription {
+            fn drop(&mut self) {
+                SYNC_LISTENERS.with(|listeners| {
+                    listeners.borrow_mut().retain(|listener| listener.id != self.id);
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+-- migrate:up
+create table if not exists entries (
+  key text not null primary key,
+  value text
+);
+
+-- migrate:down
+drop table entries;
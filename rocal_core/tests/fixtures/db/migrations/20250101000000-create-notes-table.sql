@@ -0,0 +1,10 @@
+-- migrate:up
+create table if not exists notes (
+  id integer primary key,
+  title text not null,
+  body text,
+  created_at datetime default current_timestamp
+);
+
+-- migrate:down
+drop table notes;
@@ -0,0 +1,167 @@
+mod tests {
+    use quote::quote;
+    use rocal_core::build_config;
+
+    #[test]
+    fn test_build_config_with_changeset_sync_mode() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            sync_server_endpoint: "http://127.0.0.1:3000/presigned-url",
+            database_directory_name: "local",
+            database_file_name: "local.sqlite3",
+            sync_mode: "changeset"
+        })
+        .to_string();
+
+        assert!(tokens.contains("SyncMode :: Changeset"));
+    }
+
+    #[test]
+    fn test_build_config_defaults_to_snapshot_sync_mode() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            database_directory_name: "local",
+            database_file_name: "local.sqlite3"
+        })
+        .to_string();
+
+        assert!(tokens.contains("SyncMode :: Snapshot"));
+    }
+
+    #[test]
+    fn test_build_config_with_invalid_sync_mode() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            sync_mode: "rows"
+        })
+        .to_string();
+
+        assert!(tokens.contains("compile_error"));
+    }
+
+    #[test]
+    fn test_build_config_with_passphrase_encryption() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            encryption: "passphrase"
+        })
+        .to_string();
+
+        assert!(tokens.contains("Encryption :: Passphrase"));
+    }
+
+    #[test]
+    fn test_build_config_with_encryption_and_changeset_sync_mode() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            sync_mode: "changeset",
+            encryption: "passphrase"
+        })
+        .to_string();
+
+        assert!(tokens.contains("compile_error"));
+    }
+
+    #[test]
+    fn test_build_config_with_local_storage_fallback() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            storage_fallback: "local_storage"
+        })
+        .to_string();
+
+        assert!(tokens.contains("Storage :: LocalStorage"));
+    }
+
+    #[test]
+    fn test_build_config_defaults_to_memory_storage_fallback() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+        })
+        .to_string();
+
+        assert!(tokens.contains("Storage :: Memory"));
+    }
+
+    #[test]
+    fn test_build_config_with_opfs_storage_fallback() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            storage_fallback: "opfs"
+        })
+        .to_string();
+
+        assert!(tokens.contains("compile_error"));
+    }
+
+    #[test]
+    fn test_build_config_with_history_routing_mode() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            routing_mode: "history"
+        })
+        .to_string();
+
+        assert!(tokens.contains("RoutingMode :: History"));
+    }
+
+    #[test]
+    fn test_build_config_with_invalid_routing_mode() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            routing_mode: "path"
+        })
+        .to_string();
+
+        assert!(tokens.contains("compile_error"));
+    }
+
+    #[test]
+    fn test_build_config_with_named_databases() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            database_directory_name: "local",
+            database_file_name: "local.sqlite3",
+            databases: {
+                cache: { file_name: "cache.sqlite3" },
+                drafts: { directory_name: "drafts", sync_mode: "changeset" },
+            }
+        })
+        .to_string();
+
+        assert!(tokens.contains(
+            "crate :: NamedDatabase :: new (\"cache\" . to_string () , std :: sync :: Arc :: new (Database :: new (\"local\" . to_string () , \"cache.sqlite3\" . to_string () ,)) , rocal :: rocal_core :: enums :: sync_mode :: SyncMode :: None ,)"
+        ));
+        assert!(tokens.contains(
+            "crate :: NamedDatabase :: new (\"drafts\" . to_string () , std :: sync :: Arc :: new (Database :: new (\"drafts\" . to_string () , \"drafts.sqlite3\" . to_string () ,)) , rocal :: rocal_core :: enums :: sync_mode :: SyncMode :: Changeset ,)"
+        ));
+    }
+
+    #[test]
+    fn test_build_config_with_databases_sharing_a_file() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            database_directory_name: "local",
+            database_file_name: "local.sqlite3",
+            databases: {
+                cache: { file_name: "local.sqlite3" }
+            }
+        })
+        .to_string();
+
+        assert!(tokens.contains("compile_error"));
+    }
+
+    #[test]
+    fn test_build_config_with_an_invalid_database_property() {
+        let tokens = build_config(quote! {
+            app_id: "a917e367-3484-424d-9302-f09bdaf647ae",
+            databases: {
+                cache: { migrations: "db/cache/migrations" }
+            }
+        })
+        .to_string();
+
+        assert!(tokens.contains("compile_error"));
+    }
+}
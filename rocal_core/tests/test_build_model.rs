@@ -0,0 +1,198 @@
+mod tests {
+    use proc_macro2::{TokenStream, TokenTree};
+    use quote::quote;
+    use rocal_core::build_model;
+    use rusqlite::{named_params, Connection};
+
+    // Returns the queries which a model runs, i.e. the string literals of its code starting with
+    // a SQL keyword.
+    fn queries(tokens: TokenStream) -> Vec<String> {
+        let mut queries = vec![];
+
+        for token in tokens {
+            match token {
+                TokenTree::Group(group) => queries.extend(self::queries(group.stream())),
+                TokenTree::Literal(literal) => {
+                    let literal = literal.to_string();
+
+                    if let Some(query) = literal
+                        .strip_prefix('"')
+                        .and_then(|literal| literal.strip_suffix('"'))
+                        .filter(|query| {
+                            ["select ", "insert ", "update ", "delete "]
+                                .iter()
+                                .any(|keyword| query.starts_with(keyword))
+                        })
+                    {
+                        queries.push(query.to_string());
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        queries
+    }
+
+    fn query<'a>(queries: &'a [String], prefix: &str) -> &'a str {
+        queries
+            .iter()
+            .find(|query| query.starts_with(prefix))
+            .unwrap_or_else(|| panic!("no query starts with {}", prefix))
+    }
+
+    #[test]
+    fn test_build_model_with_defaults() {
+        let tokens = build_model(quote! {
+            pub struct CartItem {
+                pub id: i64,
+                pub product_id: i64,
+                pub number_of_items: u32,
+            }
+        });
+
+        assert!(tokens
+            .to_string()
+            .contains("pub const TABLE_NAME : & 'static str = \"cart_items\" ;"));
+
+        let queries = queries(tokens);
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "create table cart_items (id integer primary key, product_id integer not null, number_of_items integer not null);",
+        )
+        .unwrap();
+
+        let inserted: (i64, i64, u32) = conn
+            .query_row(
+                query(&queries, "insert "),
+                named_params! { ":product_id": 7, ":number_of_items": 2 },
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(inserted, (1, 7, 2));
+
+        conn.execute(
+            query(&queries, "update "),
+            named_params! { ":product_id": 8, ":number_of_items": 3, ":id": 1 },
+        )
+        .unwrap();
+
+        let found: (i64, i64, u32) = conn
+            .query_row(
+                query(
+                    &queries,
+                    "select id, product_id, number_of_items from cart_items where",
+                ),
+                [1],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(found, (1, 8, 3));
+
+        let all = "select id, product_id, number_of_items from cart_items;";
+        assert!(queries.iter().any(|query| query == all));
+
+        let count = conn
+            .prepare(all)
+            .unwrap()
+            .query_map([], |row| row.get::<_, i64>(0))
+            .unwrap()
+            .count();
+        assert_eq!(count, 1);
+
+        assert_eq!(conn.execute(query(&queries, "delete "), [1]).unwrap(), 1);
+        assert_eq!(
+            conn.prepare(all)
+                .unwrap()
+                .query_map([], |_| Ok(()))
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_build_model_with_attributes() {
+        let tokens = build_model(quote! {
+            #[rocal(table = "sync_connections", primary_key = "user_id")]
+            pub struct SyncConnection {
+                pub user_id: String,
+                pub password: String,
+            }
+        });
+
+        let queries = queries(tokens);
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "create table sync_connections (user_id text primary key, password text not null);",
+        )
+        .unwrap();
+
+        // A primary key which isn't an integer is inserted with the other columns.
+        let inserted: (String, String) = conn
+            .query_row(
+                query(&queries, "insert "),
+                named_params! { ":user_id": "user", ":password": "secret" },
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(inserted, ("user".to_string(), "secret".to_string()));
+
+        conn.execute(
+            query(&queries, "update "),
+            named_params! { ":password": "changed", ":user_id": "user" },
+        )
+        .unwrap();
+
+        let password: String = conn
+            .query_row(
+                query(
+                    &queries,
+                    "select user_id, password from sync_connections where",
+                ),
+                ["user"],
+                |row| row.get(1),
+            )
+            .unwrap();
+        assert_eq!(password, "changed");
+
+        assert_eq!(
+            conn.execute(query(&queries, "delete "), ["user"]).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_build_model_table_names() {
+        let tokens = build_model(quote! {
+            pub struct Category {
+                pub id: i64,
+            }
+        })
+        .to_string();
+
+        assert!(tokens.contains("pub const TABLE_NAME : & 'static str = \"categorys\" ;"));
+
+        let tokens = build_model(quote! {
+            #[rocal(table = "categories")]
+            pub struct Category {
+                pub id: i64,
+            }
+        })
+        .to_string();
+
+        assert!(tokens.contains("pub const TABLE_NAME : & 'static str = \"categories\" ;"));
+    }
+
+    #[test]
+    fn test_build_model_without_primary_key() {
+        let tokens = build_model(quote! {
+            pub struct Note {
+                pub title: String,
+            }
+        })
+        .to_string();
+
+        assert!(tokens.contains("compile_error"));
+    }
+}
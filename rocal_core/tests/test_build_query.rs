@@ -0,0 +1,111 @@
+mod tests {
+    use proc_macro2::TokenStream;
+    use quote::quote;
+    use rocal_core::{build_query, build_query_as};
+
+    fn expand(build: fn(TokenStream) -> TokenStream, item: TokenStream) -> String {
+        std::env::set_var(
+            "CARGO_MANIFEST_DIR",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"),
+        );
+
+        build(item).to_string()
+    }
+
+    #[test]
+    fn test_build_query_infers_record() {
+        let tokens = expand(
+            build_query,
+            quote! { db, "select id, title, body, created_at from notes where id = $1;", note_id },
+        );
+
+        assert!(!tokens.contains("compile_error"));
+        assert!(tokens.contains("id : i64"));
+        assert!(tokens.contains("title : String"));
+        assert!(tokens.contains("body : Option < String >"));
+        assert!(tokens.contains("created_at : Option < String >"));
+        assert!(tokens.contains("query . bind (note_id)"));
+    }
+
+    #[test]
+    fn test_build_query_with_overridden_column() {
+        let tokens = expand(
+            build_query,
+            quote! { db, "select count(*) as \"count!: u32\", max(body) as \"body?: String\" from notes;" },
+        );
+
+        assert!(!tokens.contains("compile_error"));
+        assert!(tokens.contains("count : u32"));
+        assert!(tokens.contains("body : Option < String >"));
+    }
+
+    #[test]
+    fn test_build_query_with_expression_without_type() {
+        let tokens = expand(
+            build_query,
+            quote! { db, "select count(*) as count from notes;" },
+        );
+
+        assert!(tokens.contains("the type of column `count` cannot be inferred"));
+    }
+
+    #[test]
+    fn test_build_query_with_unknown_column() {
+        let tokens = expand(build_query, quote! { db, "select titel from notes;" });
+
+        assert!(tokens.contains("compile_error"));
+        assert!(tokens.contains("no such column: titel"));
+    }
+
+    #[test]
+    fn test_build_query_with_unknown_table() {
+        let tokens = expand(build_query, quote! { db, "select id from note;" });
+
+        assert!(tokens.contains("no such table: note"));
+    }
+
+    #[test]
+    fn test_build_query_with_wrong_number_of_parameters() {
+        let tokens = expand(
+            build_query,
+            quote! { db, "select id from notes where id = $1 and title = $2;", note_id },
+        );
+
+        assert!(tokens.contains("expected 2 parameter(s) but 1 given"));
+    }
+
+    #[test]
+    fn test_build_query_as_maps_fields() {
+        let tokens = expand(
+            build_query_as,
+            quote! { Note, db, "select id, title from notes;" },
+        );
+
+        assert!(!tokens.contains("compile_error"));
+        assert!(tokens.contains("Note { id : record . id , title : record . title }"));
+    }
+
+    #[test]
+    fn test_build_query_with_migrations_of_another_database() {
+        let tokens = expand(
+            build_query,
+            quote! { cache, "select key, value from entries where key = $1;", key, migrations: "db/cache/migrations" },
+        );
+
+        assert!(!tokens.contains("compile_error"));
+        assert!(tokens.contains("key : String"));
+        assert!(tokens.contains("value : Option < String >"));
+        assert!(tokens.contains("query . bind (key)"));
+
+        let tokens = expand(build_query, quote! { cache, "select key from entries;" });
+
+        assert!(tokens.contains("no such table: entries"));
+
+        let tokens = expand(
+            build_query,
+            quote! { cache, "select key from entries;", path: "db/cache/migrations" },
+        );
+
+        assert!(tokens.contains("expected migrations"));
+    }
+}
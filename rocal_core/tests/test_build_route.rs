@@ -0,0 +1,91 @@
+mod tests {
+    use quote::quote;
+    use rocal_core::{build_route, build_route_paths};
+
+    #[test]
+    fn test_build_route_paths_for_named_routes() {
+        let tokens = build_route_paths(quote! {
+            get "/" => { controller: RootController, action: index, view: RootView, as: root },
+            get "/notes/<note_id>/tags/<tag>" => { controller: TagsController, action: show, view: TagsView, as: note_tag },
+            post "/notes" => { controller: NotesController, action: create, view: NotesView }
+        })
+        .to_string();
+
+        assert!(tokens.contains("pub mod routes"));
+        assert!(tokens.contains("pub fn root_path () -> String { format ! (\"/\" ,) }"));
+        assert!(tokens.contains(
+            "pub fn note_tag_path (note_id : impl std :: fmt :: Display , tag : impl std :: fmt :: Display) -> String { format ! (\"/notes/{}/tags/{}\" , rocal :: rocal_core :: router :: encode_segment (note_id) , rocal :: rocal_core :: router :: encode_segment (tag)) }"
+        ));
+        assert!(!tokens.contains("create"));
+    }
+
+    #[test]
+    fn test_build_route_paths_without_named_routes() {
+        let tokens = build_route_paths(quote! {
+            get "/" => { controller: RootController, action: index, view: RootView }
+        });
+
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_build_route_with_duplicate_names() {
+        let tokens = build_route(quote! {
+            get "/" => { controller: RootController, action: index, view: RootView, as: root },
+            get "/home" => { controller: RootController, action: index, view: RootView, as: root }
+        })
+        .to_string();
+
+        assert!(tokens.contains("compile_error"));
+    }
+
+    #[test]
+    fn test_build_route_paths_with_optional_and_wildcard_segments() {
+        let tokens = build_route_paths(quote! {
+            get "/notes/<note_id>?" => { controller: NotesController, action: index, view: NotesView, as: notes },
+            get "/files/*path" => { controller: FilesController, action: show, view: FilesView, as: file }
+        })
+        .to_string();
+
+        assert!(tokens.contains(
+            "pub fn notes_path (note_id : Option < impl std :: fmt :: Display >) -> String"
+        ));
+        assert!(
+            tokens.contains("[note_id . map (rocal :: rocal_core :: router :: encode_segment)]")
+        );
+        assert!(tokens.contains(
+            "pub fn file_path (path : impl std :: fmt :: Display) -> String { format ! (\"/files/{}\" , rocal :: rocal_core :: router :: encode_path (path)) }"
+        ));
+    }
+
+    #[test]
+    fn test_build_route_with_misplaced_segments() {
+        for path in [
+            "/files/*path/edit",
+            "/notes/<note_id>?/edit",
+            "/files/*",
+            "/notes/<note_id:[0-9>",
+        ] {
+            let tokens = build_route(quote! {
+                get #path => { controller: FilesController, action: show, view: FilesView }
+            })
+            .to_string();
+
+            assert!(tokens.contains("compile_error"), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_build_route_paths_with_constrained_params() {
+        let tokens = build_route_paths(quote! {
+            get "/notes/<note_id:int>/tags/<tag:[a-z-]+>" => { controller: TagsController, action: show, view: TagsView, as: note_tag },
+            get "/archive/<page:int>?" => { controller: ArchiveController, action: index, view: ArchiveView, as: archive }
+        })
+        .to_string();
+
+        assert!(tokens.contains(
+            "pub fn note_tag_path (note_id : i64 , tag : impl std :: fmt :: Display) -> String"
+        ));
+        assert!(tokens.contains("pub fn archive_path (page : Option < i64 >) -> String"));
+    }
+}
@@ -0,0 +1,98 @@
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::{Arc, Mutex},
+    };
+
+    use rusqlite::{
+        hooks::{AuthAction, AuthContext, Authorization},
+        Connection,
+    };
+
+    // Collects the tables which the writes touch the way the query worker does, i.e. both by the
+    // update hook for each row and by the authorizer for each statement, returning how many rows
+    // the update hook has seen.
+    fn watch(conn: &Connection) -> (Arc<Mutex<BTreeSet<String>>>, Arc<Mutex<usize>>) {
+        let tables = Arc::new(Mutex::new(BTreeSet::new()));
+        let rows = Arc::new(Mutex::new(0));
+
+        {
+            let tables = tables.clone();
+            let rows = rows.clone();
+
+            conn.update_hook(Some(move |_, _: &str, table: &str, _| {
+                *rows.lock().unwrap() += 1;
+                tables.lock().unwrap().insert(table.to_string());
+            }))
+            .unwrap();
+        }
+
+        {
+            let tables = tables.clone();
+
+            conn.authorizer(Some(move |context: AuthContext<'_>| {
+                let table = match context.action {
+                    AuthAction::Insert { table_name }
+                    | AuthAction::Update { table_name, .. }
+                    | AuthAction::Delete { table_name } => Some(table_name),
+                    _ => None,
+                };
+
+                if let Some(table) = table
+                    .filter(|table| !table.starts_with("sqlite_") && !table.starts_with("_rocal_"))
+                {
+                    tables.lock().unwrap().insert(table.to_string());
+                }
+
+                Authorization::Allow
+            }))
+            .unwrap();
+        }
+
+        (tables, rows)
+    }
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+
+        conn.execute_batch(
+            "create table notes (id integer primary key, body text);
+             create table tags (id integer primary key, name text);
+             insert into notes (body) values ('a'), ('b'), ('c');
+             insert into tags (name) values ('rust');",
+        )
+        .unwrap();
+
+        conn
+    }
+
+    #[test]
+    fn test_writes_to_many_rows_notify_their_table() {
+        let conn = setup();
+        let (tables, rows) = watch(&conn);
+
+        conn.execute("update notes set body = 'd' where id > 1;", [])
+            .unwrap();
+
+        assert_eq!(*rows.lock().unwrap(), 2);
+        assert_eq!(
+            *tables.lock().unwrap(),
+            BTreeSet::from(["notes".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_delete_without_where_notifies_its_table() {
+        let conn = setup();
+        let (tables, rows) = watch(&conn);
+
+        // SQLite empties the table at once, which the update hook alone misses.
+        conn.execute("delete from notes;", []).unwrap();
+
+        assert_eq!(*rows.lock().unwrap(), 0);
+        assert_eq!(
+            *tables.lock().unwrap(),
+            BTreeSet::from(["notes".to_string()])
+        );
+    }
+}
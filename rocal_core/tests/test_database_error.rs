@@ -0,0 +1,83 @@
+mod tests {
+    use rocal_core::enums::database_error::DatabaseError;
+
+    #[test]
+    fn test_from_sqlite_classifies_constraint_violations() {
+        let err = DatabaseError::from_sqlite(2067, "UNIQUE constraint failed: users.email");
+
+        assert_eq!(
+            err,
+            DatabaseError::Constraint {
+                code: 2067,
+                message: "UNIQUE constraint failed: users.email".to_string()
+            }
+        );
+        assert!(err.is_unique_violation());
+        assert_eq!(err.get_code(), Some(2067));
+
+        let err = DatabaseError::from_sqlite(787, "FOREIGN KEY constraint failed");
+
+        assert!(matches!(err, DatabaseError::Constraint { .. }));
+        assert!(!err.is_unique_violation());
+    }
+
+    #[test]
+    fn test_from_sqlite_classifies_syntax_errors() {
+        let err = DatabaseError::from_sqlite(1, "near \"selct\": syntax error");
+
+        assert!(matches!(err, DatabaseError::Syntax { code: 1, .. }));
+        assert_eq!(err.get_message(), "near \"selct\": syntax error");
+
+        let err = DatabaseError::from_sqlite(
+            1,
+            "SQLITE_ERROR: sqlite3 result code 1: near \"selct\": syntax error",
+        );
+
+        assert!(matches!(err, DatabaseError::Syntax { code: 1, .. }));
+    }
+
+    #[test]
+    fn test_from_sqlite_keeps_other_errors_of_sqlite_error() {
+        let err = DatabaseError::from_sqlite(
+            1,
+            "SQLITE_ERROR: sqlite3 result code 1: no such table: notes",
+        );
+
+        assert!(matches!(err, DatabaseError::Sqlite { code: 1, .. }));
+        assert_eq!(
+            err.to_string(),
+            "SQLite error (1): SQLITE_ERROR: sqlite3 result code 1: no such table: notes"
+        );
+
+        let err = DatabaseError::from_sqlite(1, "table notes has no column named title");
+
+        assert!(matches!(err, DatabaseError::Sqlite { code: 1, .. }));
+    }
+
+    #[test]
+    fn test_from_sqlite_keeps_other_codes() {
+        let err = DatabaseError::from_sqlite(5, "database is locked");
+
+        assert!(matches!(err, DatabaseError::Sqlite { code: 5, .. }));
+        assert_eq!(err.to_string(), "SQLite error (5): database is locked");
+    }
+
+    #[test]
+    fn test_errors_without_code() {
+        assert_eq!(DatabaseError::OpfsUnavailable.get_code(), None);
+        assert_eq!(
+            DatabaseError::WorkerCrashed("out of memory".to_string()).to_string(),
+            "Query worker crashed: out of memory"
+        );
+        assert_eq!(DatabaseError::QueryOutsideTransaction.get_code(), None);
+        assert_eq!(DatabaseError::NotADatabase.get_code(), None);
+        assert_eq!(
+            DatabaseError::NotADatabase.to_string(),
+            "The file is not a SQLite database."
+        );
+        assert_eq!(
+            DatabaseError::MissingMigrations("20250101000000".to_string()).to_string(),
+            "The file lacks migrations of the database. Call migrate before importing it. (20250101000000)"
+        );
+    }
+}
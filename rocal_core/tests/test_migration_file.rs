@@ -0,0 +1,75 @@
+mod tests {
+    use std::{env, fs};
+
+    use rocal_core::migration_file::{parse_version, read_migration_files, split_sections};
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("20250101000000-create-notes-table.sql"),
+            Some("20250101000000".to_string())
+        );
+        assert_eq!(parse_version("create-notes-table.sql"), None);
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let (up, down) = split_sections(
+            "-- migrate:up\ncreate table notes (id integer primary key);\n\n  -- migrate:down\ndrop table notes;\n",
+        );
+
+        assert_eq!(up, "create table notes (id integer primary key);\n\n");
+        assert_eq!(down, Some("drop table notes;\n".to_string()));
+    }
+
+    #[test]
+    fn test_split_sections_without_markers() {
+        let (up, down) = split_sections("create table notes (id integer primary key);\n");
+
+        assert_eq!(up, "create table notes (id integer primary key);\n");
+        assert_eq!(down, None);
+    }
+
+    #[test]
+    fn test_read_migration_files() {
+        let dir = env::temp_dir().join(format!("rocal-migrations-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(
+            dir.join("20250102000000-create-tags-table.sql"),
+            "-- migrate:up\ncreate table tags (id integer primary key);\n-- migrate:down\ndrop table tags;\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("20250101000000-create-notes-table.sql"),
+            "create table notes (id integer primary key);\n",
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "").unwrap();
+
+        let files = read_migration_files(&dir).unwrap();
+
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| (file.get_version(), file.get_down()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("20250101000000", None),
+                ("20250102000000", Some("drop table tags;\n")),
+            ]
+        );
+
+        fs::write(dir.join("20250102000000-create-users-table.sql"), "").unwrap();
+
+        assert_eq!(
+            read_migration_files(&dir).err(),
+            Some(
+                "20250102000000-create-users-table.sql has the same version as another migration"
+                    .to_string()
+            )
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
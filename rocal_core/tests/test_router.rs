@@ -0,0 +1,303 @@
+mod tests {
+    use std::{
+        cell::RefCell,
+        collections::HashMap,
+        future::Future,
+        pin::{pin, Pin},
+        rc::Rc,
+        task::{Context, Poll, Waker},
+    };
+
+    use rocal_core::{
+        enums::{request_method::RequestMethod, routing_mode::RoutingMode},
+        router::{
+            encode_path, encode_segment, link_to, set_routing_mode, to_link_path, to_path, Router,
+        },
+    };
+    use url::Url;
+
+    type Action = Box<dyn Fn(HashMap<String, String>) -> Pin<Box<dyn Future<Output = bool>>>>;
+
+    fn action() -> Action {
+        Box::new(|_| Box::pin(async { true }))
+    }
+
+    type Resolved = Rc<RefCell<Option<(&'static str, HashMap<String, String>)>>>;
+
+    fn recording_action(name: &'static str, resolved: &Resolved) -> Action {
+        let resolved = resolved.clone();
+
+        Box::new(move |args| {
+            *resolved.borrow_mut() = Some((name, args));
+            Box::pin(async { true })
+        })
+    }
+
+    fn resolve(
+        router: &Router,
+        resolved: &Resolved,
+        path: &str,
+    ) -> Option<(&'static str, HashMap<String, String>)> {
+        let mut future = pin!(router.resolve(RequestMethod::Get, path, None));
+
+        match future
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(true) => resolved.borrow_mut().take(),
+            _ => None,
+        }
+    }
+
+    fn args(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn path_of(url: &str) -> String {
+        to_path(&Url::parse(url).unwrap())
+    }
+
+    fn link_path_of(href: &str) -> Option<String> {
+        to_link_path(
+            &Url::parse(href).unwrap(),
+            &Url::parse("http://127.0.0.1:3000/notes/1").unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_router_recognizes_registered_routes() {
+        let mut router = Router::new();
+        router.register(RequestMethod::Get, "/", action());
+        router.register(RequestMethod::Get, "/notes/<note_id>", action());
+        router.register(RequestMethod::Post, "/notes", action());
+
+        assert!(router.recognizes(RequestMethod::Get, "/"));
+        assert!(router.recognizes(RequestMethod::Get, "/?note_id=1"));
+        assert!(router.recognizes(RequestMethod::Get, "/notes/1"));
+        assert!(router.recognizes(RequestMethod::Post, "/notes"));
+        assert!(!router.recognizes(RequestMethod::Get, "/notes"));
+        assert!(!router.recognizes(RequestMethod::Get, "/notes/1/edit"));
+        assert!(!router.recognizes(RequestMethod::Delete, "/notes/1"));
+    }
+
+    #[test]
+    fn test_static_segments_take_precedence_over_params_and_wildcards() {
+        let resolved = Resolved::default();
+        let mut router = Router::new();
+        router.register(
+            RequestMethod::Get,
+            "/files/*path",
+            recording_action("files", &resolved),
+        );
+        router.register(
+            RequestMethod::Get,
+            "/notes/<note_id>",
+            recording_action("note", &resolved),
+        );
+        router.register(
+            RequestMethod::Get,
+            "/notes/new",
+            recording_action("new_note", &resolved),
+        );
+        router.register(
+            RequestMethod::Get,
+            "/notes/<note_id>/edit",
+            recording_action("edit_note", &resolved),
+        );
+        router.register(
+            RequestMethod::Get,
+            "/notes/*path",
+            recording_action("notes", &resolved),
+        );
+
+        for _ in 0..10 {
+            assert_eq!(
+                resolve(&router, &resolved, "/notes/new"),
+                Some(("new_note", args(&[])))
+            );
+            assert_eq!(
+                resolve(&router, &resolved, "/notes/1"),
+                Some(("note", args(&[("note_id", "1")])))
+            );
+            assert_eq!(
+                resolve(&router, &resolved, "/notes/1/edit"),
+                Some(("edit_note", args(&[("note_id", "1")])))
+            );
+            assert_eq!(
+                resolve(&router, &resolved, "/notes/1/tags/2"),
+                Some(("notes", args(&[("path", "1/tags/2")])))
+            );
+        }
+
+        assert_eq!(
+            resolve(&router, &resolved, "/files/docs/a.txt?v=2"),
+            Some(("files", args(&[("path", "docs/a.txt"), ("v", "2")])))
+        );
+        assert_eq!(resolve(&router, &resolved, "/files"), None);
+        assert_eq!(resolve(&router, &resolved, "/files/"), None);
+    }
+
+    #[test]
+    fn test_optional_segments() {
+        let resolved = Resolved::default();
+        let mut router = Router::new();
+        router.register(
+            RequestMethod::Get,
+            "/notes/<note_id>?",
+            recording_action("notes", &resolved),
+        );
+        router.register(
+            RequestMethod::Get,
+            "/<year>?/<month>?",
+            recording_action("archive", &resolved),
+        );
+
+        assert_eq!(
+            resolve(&router, &resolved, "/notes"),
+            Some(("notes", args(&[])))
+        );
+        assert_eq!(
+            resolve(&router, &resolved, "/notes/1"),
+            Some(("notes", args(&[("note_id", "1")])))
+        );
+        assert_eq!(resolve(&router, &resolved, "/notes/1/2"), None);
+
+        assert_eq!(
+            resolve(&router, &resolved, "/"),
+            Some(("archive", args(&[])))
+        );
+        assert_eq!(
+            resolve(&router, &resolved, "/2026/10"),
+            Some(("archive", args(&[("year", "2026"), ("month", "10")])))
+        );
+    }
+
+    #[test]
+    fn test_params_with_constraints() {
+        let resolved = Resolved::default();
+        let mut router = Router::new();
+        router.register(
+            RequestMethod::Get,
+            "/notes/<slug>",
+            recording_action("note_by_slug", &resolved),
+        );
+        router.register(
+            RequestMethod::Get,
+            "/notes/<note_id:int>",
+            recording_action("note", &resolved),
+        );
+        router.register(
+            RequestMethod::Get,
+            "/tags/<tag:[a-z-]+>",
+            recording_action("tag", &resolved),
+        );
+
+        assert_eq!(
+            resolve(&router, &resolved, "/notes/12"),
+            Some(("note", args(&[("note_id", "12")])))
+        );
+        assert_eq!(
+            resolve(&router, &resolved, "/notes/hello-world"),
+            Some(("note_by_slug", args(&[("slug", "hello-world")])))
+        );
+        assert_eq!(
+            resolve(&router, &resolved, "/tags/rust-lang"),
+            Some(("tag", args(&[("tag", "rust-lang")])))
+        );
+        assert_eq!(resolve(&router, &resolved, "/tags/Rust"), None);
+        assert_eq!(resolve(&router, &resolved, "/tags/rust1"), None);
+    }
+
+    #[test]
+    fn test_encoded_params() {
+        let resolved = Resolved::default();
+        let mut router = Router::new();
+        router.register(
+            RequestMethod::Get,
+            "/tags/<tag>",
+            recording_action("tag", &resolved),
+        );
+        router.register(
+            RequestMethod::Get,
+            "/files/*path",
+            recording_action("file", &resolved),
+        );
+
+        assert_eq!(encode_segment("c/c++ & 100%"), "c%2Fc++%20&%20100%25");
+        assert_eq!(encode_path("my docs/a#1.md"), "my%20docs/a%231.md");
+
+        let tag = format!("/tags/{}", encode_segment("c/c++ 100%"));
+        assert_eq!(
+            resolve(&router, &resolved, &tag),
+            Some(("tag", args(&[("tag", "c/c++ 100%")])))
+        );
+
+        let file = format!("/files/{}", encode_path("my docs/a#1.md"));
+        assert_eq!(
+            resolve(&router, &resolved, &file),
+            Some(("file", args(&[("path", "my docs/a#1.md")])))
+        );
+    }
+
+    #[test]
+    fn test_hash_routing_mode() {
+        set_routing_mode(RoutingMode::Hash);
+
+        assert_eq!(link_to("/notes/1", false), "/#/notes/1");
+        assert_eq!(link_to("https://rocal.dev", true), "https://rocal.dev");
+
+        assert_eq!(
+            path_of("http://127.0.0.1:3000/#/notes/1?tab=2"),
+            "/notes/1?tab=2"
+        );
+        assert_eq!(path_of("http://127.0.0.1:3000/"), "/");
+    }
+
+    #[test]
+    fn test_history_routing_mode() {
+        set_routing_mode(RoutingMode::History);
+
+        assert_eq!(link_to("/notes/1", false), "/notes/1");
+
+        assert_eq!(
+            path_of("http://127.0.0.1:3000/notes/1?tab=2"),
+            "/notes/1?tab=2"
+        );
+        assert_eq!(path_of("http://127.0.0.1:3000/notes/1#top"), "/notes/1");
+        assert_eq!(path_of("http://127.0.0.1:3000/#/notes/1"), "/notes/1");
+    }
+
+    #[test]
+    fn test_links_in_hash_routing_mode() {
+        set_routing_mode(RoutingMode::Hash);
+
+        assert_eq!(
+            link_path_of("http://127.0.0.1:3000/#/notes?note_id=2"),
+            Some("/notes?note_id=2".to_string())
+        );
+        assert_eq!(link_path_of("http://127.0.0.1:3000/notes/2"), None);
+        assert_eq!(link_path_of("http://127.0.0.1:3000/notes/1#top"), None);
+        assert_eq!(link_path_of("https://rocal.dev/#/notes"), None);
+    }
+
+    #[test]
+    fn test_links_in_history_routing_mode() {
+        set_routing_mode(RoutingMode::History);
+
+        assert_eq!(
+            link_path_of("http://127.0.0.1:3000/notes/2"),
+            Some("/notes/2".to_string())
+        );
+        assert_eq!(
+            link_path_of("http://127.0.0.1:3000/#/notes/2"),
+            Some("/notes/2".to_string())
+        );
+        assert_eq!(link_path_of("http://127.0.0.1:3000/notes/1#top"), None);
+        assert_eq!(link_path_of("http://127.0.0.1:3000/public/logo.png"), None);
+        assert_eq!(link_path_of("https://rocal.dev/notes/2"), None);
+        assert_eq!(link_path_of("mailto:yoshi@rocal.dev"), None);
+    }
+}
@@ -0,0 +1,24 @@
+mod tests {
+    use rocal_core::sql::get_param_names;
+
+    #[test]
+    fn test_get_param_names() {
+        assert_eq!(
+            get_param_names(
+                "update products set name = :name, price = @price where id = $1 and name <> :name;"
+            ),
+            vec![":name", "@price", "$1"]
+        );
+        assert!(get_param_names("select * from products;").is_empty());
+    }
+
+    #[test]
+    fn test_get_param_names_skips_literals_and_comments() {
+        assert_eq!(
+            get_param_names(
+                "select 'it''s :quoted', \"a:b\", [c:d] -- :comment\nfrom t /* :block */ where id = :id;"
+            ),
+            vec![":id"]
+        );
+    }
+}
@@ -1,14 +1,16 @@
 use std::io::prelude::*;
-use std::{env, fs};
+use std::{env, fs, path::PathBuf};
 use std::{
     net::{TcpListener, TcpStream},
     thread,
 };
 
 use models::content_type::ContentType;
+use sync_server::SyncServer;
 use utils::color::Color;
 
 mod models;
+pub mod sync_server;
 mod utils;
 
 pub fn run(port: Option<&str>) {
@@ -60,6 +62,54 @@ pub fn run(port: Option<&str>) {
     }
 }
 
+/// Runs a stand-in sync server that keeps synced databases in `directory`. It listens on `host`,
+/// loopback by default, and hands out URLs under `public_url`, which is `http://<host>:<port>` by
+/// default. Only apps served from `allowed_origins`, which are the ones of `rocal run` by default,
+/// can use it.
+pub fn run_sync_server(
+    port: Option<&str>,
+    host: Option<&str>,
+    public_url: Option<&str>,
+    directory: Option<&str>,
+    allowed_origins: Vec<String>,
+) {
+    let port = port.unwrap_or("4000");
+    let host = host.unwrap_or("127.0.0.1");
+    let public_url = public_url
+        .map(|public_url| public_url.to_string())
+        .unwrap_or_else(|| format!("http://{}:{}", host, port));
+    let directory = PathBuf::from(directory.unwrap_or("db/sync"));
+    let allowed_origins = if allowed_origins.is_empty() {
+        vec![
+            "http://127.0.0.1:3000".to_string(),
+            "http://localhost:3000".to_string(),
+        ]
+    } else {
+        allowed_origins
+    };
+
+    let listener = TcpListener::bind(format!("{}:{}", host, port)).unwrap_or_else(|_| {
+        panic!(
+            "{}",
+            Color::Red.text(&format!("Failed to listen on {}:{}.", host, port))
+        )
+    });
+
+    println!(
+        "Storing synced databases in {}",
+        Color::Cyan.text(&directory.to_string_lossy())
+    );
+    println!("Set sync_server_endpoint in config! to:");
+    println!("    {}", Color::Green.text(&public_url));
+    println!(
+        "Allowing apps served from {}",
+        Color::Cyan.text(&allowed_origins.join(", "))
+    );
+    println!("\nQuit by pressing CTRL-C");
+
+    SyncServer::new(directory, &public_url, allowed_origins).listen(listener);
+}
+
 fn handle_connection(mut stream: TcpStream) {
     let mut buffer = [0; 1024];
 
@@ -117,6 +167,20 @@ fn handle_connection(mut stream: TcpStream) {
                 "index.html"
             };
 
+            // A path without an extension is a page of the app in the history routing mode,
+            // which index.html loads.
+            let file_path = if fs::metadata(format!("./{}", file_path)).is_err()
+                && !file_path
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .contains('.')
+            {
+                "index.html"
+            } else {
+                file_path
+            };
+
             let contents = if let Ok(contents) = fs::read(&format!("./{}", file_path)) {
                 println!("[INFO] {} could be found", resource);
                 contents
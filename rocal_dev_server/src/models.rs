@@ -1 +1,3 @@
 pub mod content_type;
+pub mod http_request;
+pub mod http_response;
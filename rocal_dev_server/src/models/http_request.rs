@@ -0,0 +1,90 @@
+use std::{collections::HashMap, io::Read, net::TcpStream};
+
+pub struct HttpRequest {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Reads a whole request, including a body of `Content-Length` bytes, from the stream.
+    pub fn read(stream: &mut TcpStream) -> Result<Self, String> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0; 8192];
+
+        let header_end = loop {
+            if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end;
+            }
+
+            let size = stream.read(&mut chunk).map_err(|err| err.to_string())?;
+
+            if size == 0 {
+                return Err("The connection was closed before the request ended".to_string());
+            }
+
+            buffer.extend_from_slice(&chunk[..size]);
+        };
+
+        let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+        let mut lines = head.split("\r\n");
+
+        let request_line: Vec<&str> = lines.next().unwrap_or("").split(' ').collect();
+
+        let (method, path) = match (request_line.first(), request_line.get(1)) {
+            (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+            _ => return Err("The request line is malformed".to_string()),
+        };
+
+        let headers: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let content_length = match headers.get("content-length") {
+            Some(length) => length
+                .parse::<usize>()
+                .map_err(|_| "Content-Length is not a number".to_string())?,
+            None => 0,
+        };
+
+        let mut body = buffer.split_off(header_end + 4);
+
+        while body.len() < content_length {
+            let size = stream.read(&mut chunk).map_err(|err| err.to_string())?;
+
+            if size == 0 {
+                return Err("The connection was closed before the body ended".to_string());
+            }
+
+            body.extend_from_slice(&chunk[..size]);
+        }
+
+        body.truncate(content_length);
+
+        Ok(Self {
+            method,
+            path,
+            headers,
+            body,
+        })
+    }
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+
+    /// The path without its query string.
+    pub fn get_path(&self) -> &str {
+        self.path.split('?').next().unwrap_or("")
+    }
+
+    pub fn get_header(&self, name: &str) -> Option<&String> {
+        self.headers.get(&name.to_lowercase())
+    }
+
+    pub fn get_body(&self) -> &Vec<u8> {
+        &self.body
+    }
+}
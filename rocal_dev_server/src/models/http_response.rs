@@ -0,0 +1,74 @@
+use std::{io::Write, net::TcpStream};
+
+pub struct HttpResponse {
+    status: u16,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            content_type: None,
+            body: vec![],
+        }
+    }
+
+    pub fn with_body(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            content_type: Some(content_type.to_string()),
+            body,
+        }
+    }
+
+    pub fn get_status(&self) -> &u16 {
+        &self.status
+    }
+
+    /// Sends the response. Only an app served from `allowed_origin`, which is the origin of the
+    /// request if it's allowed, can read it from another origin.
+    pub fn send(
+        &self,
+        stream: &mut TcpStream,
+        allowed_origin: Option<&str>,
+    ) -> std::io::Result<()> {
+        let mut header = format!(
+            "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            self.reason(),
+            self.body.len()
+        );
+
+        if let Some(content_type) = &self.content_type {
+            header.push_str(&format!("Content-Type: {}\r\n", content_type));
+        }
+
+        if let Some(origin) = allowed_origin {
+            header.push_str(&format!(
+                "Access-Control-Allow-Origin: {}\r\nAccess-Control-Allow-Credentials: true\r\nAccess-Control-Allow-Methods: GET, PUT, POST, OPTIONS\r\nAccess-Control-Allow-Headers: Content-Type\r\nCross-Origin-Resource-Policy: cross-origin\r\n",
+                origin
+            ));
+        }
+
+        header.push_str("Vary: Origin\r\n\r\n");
+
+        stream.write_all(header.as_bytes())?;
+        stream.write_all(&self.body)?;
+        stream.flush()
+    }
+
+    fn reason(&self) -> &str {
+        match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            _ => "Internal Server Error",
+        }
+    }
+}
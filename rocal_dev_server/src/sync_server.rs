@@ -0,0 +1,438 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    models::{http_request::HttpRequest, http_response::HttpResponse},
+    utils::color::Color,
+};
+
+const GRANT_TTL: Duration = Duration::from_secs(15 * 60);
+const CHANGESETS_DIRECTORY_NAME: &str = "changesets";
+
+/// A stand-in for the sync server of Rocal platform that keeps synced databases in a local
+/// directory laid out as `<app_id>/<user_id>/<file_name>`.
+///
+/// Like the hosted one, it answers a sync request with one-off URLs to GET or PUT the
+/// database and its hash, which expire after 15 minutes. Passwords are not checked, so when it
+/// listens on an address other than loopback, that address should only be reachable from
+/// trusted networks. Only apps served from the allowed origins can read its responses.
+pub struct SyncServer {
+    directory: PathBuf,
+    base_url: String,
+    allowed_origins: Vec<String>,
+    grants: Mutex<HashMap<String, Grant>>,
+    changesets: Mutex<()>,
+}
+
+struct Grant {
+    method: &'static str,
+    path: PathBuf,
+    expires_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct SyncRequest {
+    app_id: Value,
+    user_id: Value,
+    file_name: Option<String>,
+    hash: Option<String>,
+    base_hash: Option<String>,
+    action: Option<String>,
+    device_id: Option<String>,
+    cursor: Option<Value>,
+    changeset: Option<String>,
+}
+
+impl SyncServer {
+    /// `base_url` is where the server is reachable from the app, e.g. `http://127.0.0.1:4000`,
+    /// and `allowed_origins` where the app is served from, e.g. `http://127.0.0.1:3000`.
+    pub fn new(directory: PathBuf, base_url: &str, allowed_origins: Vec<String>) -> Self {
+        Self {
+            directory,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            allowed_origins: allowed_origins
+                .into_iter()
+                .map(|origin| origin.trim_end_matches('/').to_string())
+                .collect(),
+            grants: Mutex::new(HashMap::new()),
+            changesets: Mutex::new(()),
+        }
+    }
+
+    pub fn listen(self, listener: TcpListener) {
+        let server = Arc::new(self);
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let server = server.clone();
+                    thread::spawn(move || {
+                        server.handle_connection(stream);
+                    });
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        Color::Red.text(&format!("[ERROR] Connection failed: {}", e))
+                    );
+                }
+            }
+        }
+    }
+
+    fn handle_connection(&self, mut stream: TcpStream) {
+        let request = match HttpRequest::read(&mut stream) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    Color::Red.text(&format!("[ERROR] Failed to read a request: {}", e))
+                );
+                let _ = HttpResponse::new(400).send(&mut stream, None);
+                return;
+            }
+        };
+
+        let response = self.handle(&request);
+
+        println!(
+            "[INFO] {} {} {}",
+            request.get_method(),
+            request.get_path(),
+            response.get_status()
+        );
+
+        let allowed_origin = request
+            .get_header("origin")
+            .filter(|origin| self.allowed_origins.contains(origin))
+            .map(|origin| origin.as_str());
+
+        if let Err(e) = response.send(&mut stream, allowed_origin) {
+            eprintln!(
+                "{}",
+                Color::Red.text(&format!("[ERROR] Failed to send a response: {}", e))
+            );
+        }
+    }
+
+    fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        if request.get_method() == "OPTIONS" {
+            return HttpResponse::new(204);
+        }
+
+        if let Some(token) = request.get_path().strip_prefix("/objects/") {
+            return match request.get_method() {
+                "GET" => self.get_object(token),
+                "PUT" => self.put_object(token, request.get_body()),
+                _ => HttpResponse::new(405),
+            };
+        }
+
+        if request.get_method() != "POST" {
+            return HttpResponse::new(405);
+        }
+
+        let sync_request: SyncRequest = match serde_json::from_slice(request.get_body()) {
+            Ok(sync_request) => sync_request,
+            Err(_) => return HttpResponse::new(400),
+        };
+
+        let user_directory = match (
+            to_path_segment(&sync_request.app_id),
+            to_path_segment(&sync_request.user_id),
+        ) {
+            (Some(app_id), Some(user_id)) => self.directory.join(app_id).join(user_id),
+            _ => return HttpResponse::new(400),
+        };
+
+        match sync_request.action.as_deref() {
+            Some("pull_changesets") => self.pull_changesets(&user_directory, &sync_request),
+            Some("push_changeset") => self.push_changeset(&user_directory, &sync_request),
+            _ => self.sync_object(&user_directory, &sync_request),
+        }
+    }
+
+    /// Decides which side holds the newer database by the SHA-256 hashes of the copy on the
+    /// device, the copy the device last synced with and the copy on this server. When both the
+    /// device and this server have changed since the last sync, the copy on this server wins.
+    fn sync_object(&self, user_directory: &Path, sync_request: &SyncRequest) -> HttpResponse {
+        let file_name = match sync_request
+            .file_name
+            .as_ref()
+            .and_then(|file_name| to_object_name(file_name))
+        {
+            Some(file_name) => file_name,
+            None => return HttpResponse::new(400),
+        };
+
+        let object_path = user_directory.join(&file_name);
+        let hash_path = user_directory.join(format!("{}.hash", file_name));
+
+        let local = sync_request.hash.as_deref();
+        let base = sync_request.base_hash.as_deref();
+        let remote = if object_path.exists() {
+            Some(
+                fs::read_to_string(&hash_path)
+                    .map(|hash| hash.trim().to_string())
+                    .unwrap_or_default(),
+            )
+        } else {
+            None
+        };
+
+        let method = match (local, remote.as_deref()) {
+            (None, None) => None,
+            (Some(_), None) => Some("PUT"),
+            (None, Some(_)) => Some("GET"),
+            (Some(local), Some(remote)) if local == remote => None,
+            (Some(local), Some(_)) if base == Some(local) => Some("GET"),
+            (Some(_), Some(remote)) if base == Some(remote) => Some("PUT"),
+            (Some(_), Some(_)) => Some("GET"),
+        };
+
+        let method = match method {
+            Some(method) => method,
+            None => {
+                return to_json_response(json!({
+                    "presigned_url": null,
+                    "hash_url": null,
+                    "action": null,
+                }))
+            }
+        };
+
+        let action = if method == "GET" {
+            "get_object"
+        } else {
+            "put_object"
+        };
+
+        to_json_response(json!({
+            "presigned_url": self.grant(method, object_path),
+            "hash_url": self.grant(method, hash_path),
+            "action": action,
+        }))
+    }
+
+    /// Returns the changesets pushed from the other devices after the cursor.
+    fn pull_changesets(&self, user_directory: &Path, sync_request: &SyncRequest) -> HttpResponse {
+        let device_id = match sync_request.device_id.as_ref() {
+            Some(device_id) => device_id,
+            None => return HttpResponse::new(400),
+        };
+        let cursor = to_cursor(&sync_request.cursor);
+
+        let _lock = self.changesets.lock().unwrap();
+
+        let directory = match changesets_directory(user_directory, sync_request) {
+            Some(directory) => directory,
+            None => return HttpResponse::new(400),
+        };
+
+        let entries = match read_changesets(&directory) {
+            Ok(entries) => entries,
+            Err(_) => return HttpResponse::new(500),
+        };
+
+        let latest = entries.last().map(|entry| entry.0).unwrap_or(0);
+
+        let changesets: Vec<String> = entries
+            .into_iter()
+            .filter(|(seq, origin, _)| cursor < *seq && origin != device_id)
+            .filter_map(|(_, _, path)| fs::read_to_string(path).ok())
+            .collect();
+
+        to_json_response(json!({ "changesets": changesets, "cursor": latest }))
+    }
+
+    /// Appends a changeset, refusing it with 409 Conflict when another device has pushed since
+    /// the cursor so that the device pulls those changes first.
+    fn push_changeset(&self, user_directory: &Path, sync_request: &SyncRequest) -> HttpResponse {
+        let (device_id, changeset) = match (
+            sync_request
+                .device_id
+                .as_ref()
+                .and_then(|device_id| to_path_segment(&Value::String(device_id.clone()))),
+            sync_request.changeset.as_ref(),
+        ) {
+            (Some(device_id), Some(changeset)) => (device_id, changeset),
+            _ => return HttpResponse::new(400),
+        };
+        let cursor = to_cursor(&sync_request.cursor);
+
+        let _lock = self.changesets.lock().unwrap();
+
+        let directory = match changesets_directory(user_directory, sync_request) {
+            Some(directory) => directory,
+            None => return HttpResponse::new(400),
+        };
+
+        let latest = match read_changesets(&directory) {
+            Ok(entries) => entries.last().map(|entry| entry.0).unwrap_or(0),
+            Err(_) => return HttpResponse::new(500),
+        };
+
+        if cursor != latest {
+            return HttpResponse::new(409);
+        }
+
+        let seq = latest + 1;
+
+        if fs::create_dir_all(&directory).is_err()
+            || fs::write(
+                directory.join(format!("{:020}-{}", seq, device_id)),
+                changeset,
+            )
+            .is_err()
+        {
+            return HttpResponse::new(500);
+        }
+
+        to_json_response(json!({ "cursor": seq }))
+    }
+
+    fn get_object(&self, token: &str) -> HttpResponse {
+        let path = match self.redeem(token, "GET") {
+            Some(path) => path,
+            None => return HttpResponse::new(403),
+        };
+
+        match fs::read(&path) {
+            Ok(contents) => HttpResponse::with_body(200, "application/octet-stream", contents),
+            Err(_) => HttpResponse::new(404),
+        }
+    }
+
+    fn put_object(&self, token: &str, body: &[u8]) -> HttpResponse {
+        let path = match self.redeem(token, "PUT") {
+            Some(path) => path,
+            None => return HttpResponse::new(403),
+        };
+
+        let result = match path.parent() {
+            Some(parent) => fs::create_dir_all(parent).and_then(|_| fs::write(&path, body)),
+            None => fs::write(&path, body),
+        };
+
+        match result {
+            Ok(_) => HttpResponse::new(200),
+            Err(_) => HttpResponse::new(500),
+        }
+    }
+
+    fn grant(&self, method: &'static str, path: PathBuf) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let now = Instant::now();
+
+        let mut grants = self.grants.lock().unwrap();
+        grants.retain(|_, grant| now < grant.expires_at);
+        grants.insert(
+            token.clone(),
+            Grant {
+                method,
+                path,
+                expires_at: now + GRANT_TTL,
+            },
+        );
+
+        format!("{}/objects/{}", self.base_url, token)
+    }
+
+    fn redeem(&self, token: &str, method: &str) -> Option<PathBuf> {
+        let mut grants = self.grants.lock().unwrap();
+
+        match grants.get(token) {
+            Some(grant) if grant.method == method && Instant::now() < grant.expires_at => {
+                grants.remove(token).map(|grant| grant.path)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Only ids made of alphanumerics, `-`, `_` and `.` are accepted so that they can't point
+/// outside of the directory.
+fn to_path_segment(value: &Value) -> Option<String> {
+    let segment = match value {
+        Value::String(segment) => segment.clone(),
+        Value::Number(segment) => segment.to_string(),
+        _ => return None,
+    };
+
+    let is_valid = !segment.is_empty()
+        && !segment.starts_with('.')
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+
+    if is_valid {
+        Some(segment)
+    } else {
+        None
+    }
+}
+
+/// A database file can't take the name of the changesets directory or of the hash of another
+/// file, which are kept next to it.
+fn to_object_name(file_name: &str) -> Option<String> {
+    to_path_segment(&Value::String(file_name.to_string())).filter(|file_name| {
+        file_name != CHANGESETS_DIRECTORY_NAME && !file_name.ends_with(".hash")
+    })
+}
+
+fn to_cursor(value: &Option<Value>) -> u64 {
+    match value {
+        Some(Value::Number(cursor)) => cursor.as_u64().unwrap_or(0),
+        Some(Value::String(cursor)) => cursor.parse().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Changesets are kept apart for each database file, under `changesets/<file_name>`.
+fn changesets_directory(user_directory: &Path, sync_request: &SyncRequest) -> Option<PathBuf> {
+    let directory = user_directory.join(CHANGESETS_DIRECTORY_NAME);
+
+    match &sync_request.file_name {
+        Some(file_name) => to_path_segment(&Value::String(file_name.clone()))
+            .map(|file_name| directory.join(file_name)),
+        None => Some(directory),
+    }
+}
+
+/// Lists `(seq, device_id, path)` of the stored changesets in the order they were pushed.
+fn read_changesets(directory: &Path) -> std::io::Result<Vec<(u64, String, PathBuf)>> {
+    if !directory.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut entries: Vec<(u64, String, PathBuf)> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let (seq, device_id) = file_name.split_once('-')?;
+
+            Some((seq.parse().ok()?, device_id.to_string(), entry.path()))
+        })
+        .collect();
+
+    entries.sort_by_key(|entry| entry.0);
+
+    Ok(entries)
+}
+
+/// The response body is a JSON encoded string of JSON, as the hosted sync server returns.
+fn to_json_response(value: Value) -> HttpResponse {
+    let body = serde_json::to_string(&value.to_string()).unwrap_or_default();
+
+    HttpResponse::with_body(200, "application/json", body.into_bytes())
+}
@@ -0,0 +1,305 @@
+mod tests {
+    use std::{
+        env,
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        path::PathBuf,
+        thread,
+    };
+
+    use rocal_dev_server::sync_server::SyncServer;
+    use serde_json::{json, Value};
+
+    fn start() -> (String, PathBuf) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let directory = env::temp_dir().join(format!("rocal-sync-{}", uuid::Uuid::new_v4()));
+
+        let server = SyncServer::new(
+            directory.clone(),
+            &base_url,
+            vec!["http://127.0.0.1:3000".to_string()],
+        );
+        thread::spawn(move || server.listen(listener));
+
+        (base_url, directory)
+    }
+
+    fn request(url: &str, method: &str, body: &[u8]) -> (u16, Vec<u8>) {
+        let rest = url.strip_prefix("http://").unwrap();
+        let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let path = if path.is_empty() { "/" } else { path };
+
+        let mut stream = TcpStream::connect(host).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n\r\n",
+            method,
+            path,
+            host,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+
+        let mut response = vec![];
+        stream.read_to_end(&mut response).unwrap();
+
+        let header_end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap();
+        let status = String::from_utf8_lossy(&response[9..12]).parse().unwrap();
+
+        (status, response[header_end + 4..].to_vec())
+    }
+
+    fn post(base_url: &str, body: Value) -> (u16, Value) {
+        let (status, body) = request(base_url, "POST", body.to_string().as_bytes());
+
+        let body = if body.is_empty() {
+            Value::Null
+        } else {
+            let json: String = serde_json::from_slice(&body).unwrap();
+            serde_json::from_str(&json).unwrap()
+        };
+
+        (status, body)
+    }
+
+    fn sync(base_url: &str, hash: Option<&str>, base_hash: Option<&str>) -> Value {
+        post(
+            base_url,
+            json!({
+                "app_id": "app",
+                "user_id": "user",
+                "password": "password",
+                "file_name": "local.sqlite3",
+                "hash": hash,
+                "base_hash": base_hash,
+            }),
+        )
+        .1
+    }
+
+    fn push(base_url: &str, hash: &str, base_hash: Option<&str>) {
+        let put = sync(base_url, Some(hash), base_hash);
+        assert_eq!(put["action"], "put_object");
+
+        let (status, _) = request(put["presigned_url"].as_str().unwrap(), "PUT", b"SQLite");
+        assert_eq!(status, 200);
+        let (status, _) = request(put["hash_url"].as_str().unwrap(), "PUT", hash.as_bytes());
+        assert_eq!(status, 200);
+    }
+
+    #[test]
+    fn test_sync_puts_and_gets_the_database() {
+        let (base_url, directory) = start();
+
+        assert_eq!(sync(&base_url, None, None)["action"], Value::Null);
+
+        push(&base_url, "a", None);
+
+        assert_eq!(
+            std::fs::read(directory.join("app/user/local.sqlite3")).unwrap(),
+            b"SQLite"
+        );
+
+        assert_eq!(sync(&base_url, Some("a"), Some("a"))["action"], Value::Null);
+
+        let get = sync(&base_url, None, None);
+        assert_eq!(get["action"], "get_object");
+
+        let (status, body) = request(get["presigned_url"].as_str().unwrap(), "GET", b"");
+        assert_eq!(status, 200);
+        assert_eq!(body, b"SQLite");
+
+        let (status, _) = request(get["presigned_url"].as_str().unwrap(), "GET", b"");
+        assert_eq!(status, 403);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_sync_decides_by_hashes() {
+        let (base_url, directory) = start();
+
+        push(&base_url, "a", None);
+
+        // Changed only on the device
+        push(&base_url, "b", Some("a"));
+
+        // Changed only on the server
+        assert_eq!(
+            sync(&base_url, Some("a"), Some("a"))["action"],
+            "get_object"
+        );
+
+        // Changed on both
+        assert_eq!(
+            sync(&base_url, Some("c"), Some("a"))["action"],
+            "get_object"
+        );
+
+        // The same changes on both
+        assert_eq!(sync(&base_url, Some("b"), Some("a"))["action"], Value::Null);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_sync_rejects_ids_pointing_outside_of_the_directory() {
+        let (base_url, _) = start();
+
+        let (status, _) = post(
+            &base_url,
+            json!({
+                "app_id": "..",
+                "user_id": "user",
+                "file_name": "local.sqlite3",
+                "hash": "a",
+            }),
+        );
+
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn test_sync_rejects_file_names_of_changesets_and_hashes() {
+        let (base_url, _) = start();
+
+        for file_name in ["changesets", "local.sqlite3.hash"] {
+            let (status, _) = post(
+                &base_url,
+                json!({
+                    "app_id": "app",
+                    "user_id": "user",
+                    "file_name": file_name,
+                    "hash": "a",
+                }),
+            );
+
+            assert_eq!(status, 400);
+        }
+    }
+
+    #[test]
+    fn test_changesets_are_pulled_by_the_other_devices() {
+        let (base_url, _) = start();
+
+        let pull = |device_id: &str, cursor: u64| {
+            post(
+                &base_url,
+                json!({
+                    "app_id": "app",
+                    "user_id": "user",
+                    "device_id": device_id,
+                    "action": "pull_changesets",
+                    "cursor": cursor.to_string(),
+                }),
+            )
+        };
+
+        let push = |device_id: &str, cursor: u64, changeset: &str| {
+            post(
+                &base_url,
+                json!({
+                    "app_id": "app",
+                    "user_id": "user",
+                    "device_id": device_id,
+                    "action": "push_changeset",
+                    "cursor": cursor,
+                    "changeset": changeset,
+                }),
+            )
+        };
+
+        let (_, pulled) = pull("a", 0);
+        assert_eq!(pulled, json!({ "changesets": [], "cursor": 0 }));
+
+        let (status, pushed) = push("a", 0, "AAAA");
+        assert_eq!(status, 200);
+        assert_eq!(pushed["cursor"], 1);
+
+        let (status, _) = push("b", 0, "BBBB");
+        assert_eq!(status, 409);
+
+        let (_, pulled) = pull("b", 0);
+        assert_eq!(pulled, json!({ "changesets": ["AAAA"], "cursor": 1 }));
+
+        let (_, pulled) = pull("a", 0);
+        assert_eq!(pulled, json!({ "changesets": [], "cursor": 1 }));
+
+        let (status, pushed) = push("b", 1, "BBBB");
+        assert_eq!(status, 200);
+        assert_eq!(pushed["cursor"], 2);
+
+        let (_, pulled) = pull("a", 1);
+        assert_eq!(pulled, json!({ "changesets": ["BBBB"], "cursor": 2 }));
+    }
+
+    #[test]
+    fn test_changesets_are_kept_apart_for_each_database() {
+        let (base_url, _) = start();
+
+        let (status, _) = post(
+            &base_url,
+            json!({
+                "app_id": "app",
+                "user_id": "user",
+                "device_id": "a",
+                "file_name": "local.sqlite3",
+                "action": "push_changeset",
+                "cursor": 0,
+                "changeset": "AAAA",
+            }),
+        );
+        assert_eq!(status, 200);
+
+        let (_, pulled) = post(
+            &base_url,
+            json!({
+                "app_id": "app",
+                "user_id": "user",
+                "device_id": "b",
+                "file_name": "cache.sqlite3",
+                "action": "pull_changesets",
+                "cursor": 0,
+            }),
+        );
+        assert_eq!(pulled, json!({ "changesets": [], "cursor": 0 }));
+    }
+
+    fn preflight(base_url: &str, origin: &str) -> String {
+        let host = base_url.strip_prefix("http://").unwrap();
+
+        let mut stream = TcpStream::connect(host).unwrap();
+        write!(
+            stream,
+            "OPTIONS / HTTP/1.1\r\nHost: {}\r\nOrigin: {}\r\nContent-Length: 0\r\n\r\n",
+            host, origin
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        response
+    }
+
+    #[test]
+    fn test_only_allowed_origins_can_read_responses() {
+        let (base_url, directory) = start();
+
+        let response = preflight(&base_url, "http://127.0.0.1:3000");
+        assert!(response.contains("Access-Control-Allow-Origin: http://127.0.0.1:3000\r\n"));
+        assert!(response.contains("Access-Control-Allow-Credentials: true\r\n"));
+
+        let response = preflight(&base_url, "https://evil.example.com");
+        assert!(response.starts_with("HTTP/1.1 204"));
+        assert!(!response.contains("Access-Control-Allow-Origin"));
+        assert!(!response.contains("Access-Control-Allow-Credentials"));
+
+        let _ = std::fs::remove_dir_all(directory);
+    }
+}
@@ -4,7 +4,10 @@ use proc_macro::TokenStream;
 use rocal_ui::build_ui;
 
 #[cfg(feature = "full")]
-use rocal_core::{build_action, build_config, build_route, run_migration, start_app};
+use rocal_core::{
+    build_action, build_config, build_model, build_query, build_query_as, build_route,
+    run_migration, start_app,
+};
 
 /// This attribute macro should be used when you create an entrypoint of a Rocal application.
 ///
@@ -55,10 +58,24 @@ pub fn action(_: TokenStream, item: TokenStream) -> TokenStream {
 
 /// This function-like macro sets up application routing.
 ///
+/// A route named with `as` gets a helper in `crate::routes` which makes its path, taking one
+/// argument for each `<param>` segment, e.g. `routes::user_path(user_id)` for the route below.
+///
+/// A path can end with optional params like `<page>?`, which the helper takes as `Option`s, or
+/// with a wildcard like `*path` matching the rest of the path. The helper takes an `int` param
+/// as an `i64` and percent-encodes every param.
+/// Static segments are matched before params, and params before wildcards.
+///
+/// A param can be constrained with `int` or a regex, e.g. `<user_id:int>` or `<slug:[a-z-]+>`,
+/// so that a segment not matching it falls through to other routes. Constrained params are
+/// matched before unconstrained ones.
+///
 /// ```rust
 /// route! {
 ///     get "/" => { controller: RootController , action: index , view: RootView },
-///     post "/users" => { controller: UsersController, action: create, view: UserView}
+///     post "/users" => { controller: UsersController, action: create, view: UserView},
+///     get "/users/<user_id:int>" => { controller: UsersController, action: show, view: UserView, as: user },
+///     get "/files/*path" => { controller: FilesController, action: show, view: FileView }
 /// }
 ///
 /// ```
@@ -68,14 +85,41 @@ pub fn route(item: TokenStream) -> TokenStream {
     build_route(item.into()).into()
 }
 
-/// This function-like macro makes `static CONFIG` which contains app_id, a connection of an embedded database, and sync server endpoint URL.
+/// This function-like macro makes `static CONFIG` which contains app_id, a connection of an
+/// embedded database, and sync server endpoint URL.
+///
+/// `sync_mode` is either `"snapshot"` (default), which uploads or downloads the whole database
+/// file, or `"changeset"`, which pushes and pulls only changed rows so that edits made offline on
+/// several devices are merged. Conflicts can be resolved with `Database::set_conflict_resolver`.
+///
+/// `encryption: "passphrase"` encrypts snapshots on the device before they are uploaded, with a key
+/// derived from the passphrase given to `SyncHandle::set_passphrase`, so the sync server never
+/// sees the contents of the database.
+///
+/// Databases are kept in OPFS. When the browser doesn't support it, `storage_fallback` keeps them
+/// in `"memory"` (default), `"local_storage"` or `"session_storage"` instead, or `"none"` to fail
+/// every query. Only the default database fits in `"local_storage"` or `"session_storage"`, so the
+/// others are kept in memory. `Database::storage` tells where a database has ended up.
+///
+/// `routing_mode: "history"` makes URLs like `/notes/1` instead of `/#/notes/1`, which needs the
+/// server to answer every path of the app with index.html, `<base href="/">` in index.html and
+/// the service worker registered as `./sw.js?routing_mode=history`, so the app has to be served
+/// at the root of its origin.
+///
+/// More databases can be declared in `databases`, each with its own `directory_name`,
+/// `file_name` (`<name>.sqlite3` by default) and `sync_mode`, which defaults to `"none"` so that
+/// the database stays on the device. They are opened with `CONFIG.database("cache")`.
 ///
 /// ```rust
 /// config! {
 ///     app_id: "a917e367-3484-424d-9302-f09bdaf647ae" ,
 ///     sync_server_endpoint: "http://127.0.0.1:3000/presigned-url" ,
 ///     database_directory_name: "local" ,
-///     database_file_name: "local.sqlite3"
+///     database_file_name: "local.sqlite3" ,
+///     sync_mode: "changeset" ,
+///     databases: {
+///         cache: { file_name: "cache.sqlite3" }
+///     }
 /// }
 /// ```
 #[cfg(feature = "full")]
@@ -86,8 +130,15 @@ pub fn config(item: TokenStream) -> TokenStream {
 
 /// This function-like macro allows users to set a path where migration files are supposed to be.
 ///
+/// Each file is applied only once, in order of its timestamp prefix, inside a transaction and
+/// recorded in the `schema_migrations` table. When a file has `-- migrate:up` and
+/// `-- migrate:down` sections, only the up section is applied here.
+///
+/// Migrations of a database declared in `databases` of `config!` are given with its name.
+///
 /// ```rust
 /// migrate!("db/migrations");
+/// migrate!("db/cache/migrations", database: "cache");
 /// ```
 #[cfg(feature = "full")]
 #[proc_macro]
@@ -95,6 +146,78 @@ pub fn migrate(item: TokenStream) -> TokenStream {
     run_migration(item.into()).into()
 }
 
+/// This derive macro generates `find`, `all`, `insert`, `update`, and `delete` for a model
+/// backed by a table of the embedded database. The model also has to derive `Deserialize`.
+///
+/// The table name defaults to the snake case of the struct name followed by `s`, e.g.
+/// `cart_items` for `CartItem`. No other plural is made, so a struct like `Category` or `Person`
+/// needs `table = "categories"` or `table = "people"`. The primary key defaults to `id`. An
+/// integer primary key is left to the database on insert unless `auto_increment = false` is set.
+///
+/// ```rust
+/// #[derive(Deserialize, rocal::Model)]
+/// #[rocal(table = "notes", primary_key = "id")]
+/// pub struct Note {
+///     pub id: i64,
+///     pub title: Option<String>,
+///     pub body: Option<String>,
+/// }
+///
+/// let note = Note::find(&database, 1).await?;
+/// ```
+#[cfg(feature = "full")]
+#[proc_macro_derive(Model, attributes(rocal))]
+pub fn model(item: TokenStream) -> TokenStream {
+    build_model(item.into()).into()
+}
+
+/// This function-like macro checks a query against the schema built from `db/migrations` at
+/// compile time. Unknown tables or columns and a wrong number of parameters become compile
+/// errors, and each row is deserialized into a record inferred from the result columns.
+///
+/// The type or nullability of a column can be overridden with its alias such as
+/// `count(*) as "count!: u32"`, where `!` means not null and `?` means nullable.
+///
+/// A query of a database with other migrations, e.g. one declared under `databases`, is
+/// checked against them with `migrations:` after the arguments, which is the path given to
+/// `migrate!`.
+///
+/// ```rust
+/// let notes = query!(database, "select id, title from notes where id = $1;", note_id)
+///     .fetch()
+///     .await?;
+///
+/// let title: Option<String> = notes[0].title.clone();
+///
+/// let entries = query!(cache, "select key from entries;", migrations: "db/cache/migrations")
+///     .fetch()
+///     .await?;
+/// ```
+#[cfg(feature = "full")]
+#[proc_macro]
+pub fn query(item: TokenStream) -> TokenStream {
+    build_query(item.into()).into()
+}
+
+/// This function-like macro works like `query!` but maps each row to the given struct, whose
+/// fields must match the result columns in name and type.
+///
+/// ```rust
+/// let note: Option<Note> = query_as!(
+///     Note,
+///     database,
+///     "select id, title, body from notes where id = $1;",
+///     note_id
+/// )
+/// .fetch_optional()
+/// .await?;
+/// ```
+#[cfg(feature = "full")]
+#[proc_macro]
+pub fn query_as(item: TokenStream) -> TokenStream {
+    build_query_as(item.into()).into()
+}
+
 /// This function-like macro generates code to produce HTML string.
 ///
 /// ```rust
@@ -0,0 +1,77 @@
+mod tests {
+    use std::{
+        cell::Cell,
+        collections::HashMap,
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    #[derive(Default)]
+    struct NotesController {
+        shown: Cell<Option<(i64, Option<u32>)>>,
+    }
+
+    impl NotesController {
+        #[rocal_macro::action]
+        pub fn show(&self, note_id: i64, page: Option<u32>) {
+            self.shown.set(Some((note_id, page)));
+        }
+
+        #[rocal_macro::action]
+        pub fn archive(&self, month: u32) {
+            if month > 12 {
+                return;
+            }
+
+            self.shown.set(Some((month as i64, None)));
+        }
+    }
+
+    fn block_on(future: impl Future<Output = bool>) -> bool {
+        match pin!(future)
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+        {
+            Poll::Ready(result) => result,
+            Poll::Pending => panic!("the action should be ready"),
+        }
+    }
+
+    fn args(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_action_parses_its_args() {
+        let controller = NotesController::default();
+
+        assert!(block_on(
+            controller.show(args(&[("note_id", "-1"), ("page", "2")]))
+        ));
+        assert_eq!(controller.shown.get(), Some((-1, Some(2))));
+
+        assert!(block_on(
+            controller.show(args(&[("note_id", "1"), ("page", "x")]))
+        ));
+        assert_eq!(controller.shown.get(), Some((1, None)));
+    }
+
+    #[test]
+    fn test_action_does_not_run_with_args_out_of_range() {
+        let controller = NotesController::default();
+
+        assert!(!block_on(
+            controller.show(args(&[("note_id", "99999999999999999999")]))
+        ));
+        assert!(!block_on(controller.archive(args(&[("month", "-1")]))));
+        assert_eq!(controller.shown.get(), None);
+
+        assert!(!block_on(controller.archive(args(&[]))));
+        assert!(block_on(controller.archive(args(&[("month", "13")]))));
+        assert_eq!(controller.shown.get(), None);
+    }
+}